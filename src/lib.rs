@@ -3,3 +3,39 @@ pub mod mcp;
 pub mod tui;
 
 pub use mcp::McpClient;
+
+use anyhow::{Context, Result};
+
+/// How to reach the server being inspected.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum ConnectSpec {
+    /// Spawn `command` and speak MCP over its stdin/stdout.
+    Stdio { command: String, args: Vec<String> },
+}
+
+impl ConnectSpec {
+    pub fn stdio(command: impl Into<String>, args: Vec<String>) -> Self {
+        ConnectSpec::Stdio {
+            command: command.into(),
+            args,
+        }
+    }
+}
+
+/// Connect to a server and complete the initialize handshake, returning a
+/// client ready for listing and calling tools, prompts and resources.
+pub async fn connect(spec: ConnectSpec) -> Result<McpClient> {
+    match spec {
+        ConnectSpec::Stdio { command, args } => {
+            let client = McpClient::new(&command, &args)
+                .await
+                .context("Failed to create MCP client")?;
+            client
+                .initialize()
+                .await
+                .context("Failed to initialize MCP client")?;
+            Ok(client)
+        }
+    }
+}
@@ -1,16 +1,44 @@
+use regex::Regex;
 use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use tracing::Level;
 use tracing::{Event, Subscriber};
 use tracing_subscriber::layer::Context;
 use tracing_subscriber::Layer;
 
+/// What each `--redact-pattern` match is replaced with.
+const REDACTED: &str = "****";
+
+/// Mask every match of `patterns` in `text`.
+pub fn redact(text: String, patterns: &[Regex]) -> String {
+    patterns.iter().fold(text, |text, pattern| {
+        match pattern.replace_all(&text, REDACTED) {
+            std::borrow::Cow::Borrowed(_) => text,
+            std::borrow::Cow::Owned(redacted) => redacted,
+        }
+    })
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LogEntry {
     pub timestamp: String,
     pub level: String,
     pub target: String,
     pub message: String,
+    /// JSON-RPC request id, when the event relates to a specific request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    /// JSON-RPC method, when the event relates to a specific request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub method: Option<String>,
+    /// UI action this entry marks (e.g. `call_tool`), for correlating
+    /// exported logs with what was done in mcpeek
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action: Option<String>,
 }
 
 impl LogEntry {
@@ -23,6 +51,9 @@ impl LogEntry {
             level: level.to_string(),
             target: target.to_string(),
             message,
+            request_id: None,
+            method: None,
+            action: None,
         }
     }
 }
@@ -30,12 +61,15 @@ impl LogEntry {
 #[derive(Clone, Default)]
 pub struct LogBuffer {
     entries: Arc<Mutex<Vec<LogEntry>>>,
+    // Bumped on every change, so readers can skip copying an unchanged buffer
+    version: Arc<AtomicU64>,
 }
 
 impl LogBuffer {
     pub fn new() -> Self {
         Self {
             entries: Arc::new(Mutex::new(Vec::new())),
+            version: Arc::new(AtomicU64::new(0)),
         }
     }
 
@@ -46,9 +80,15 @@ impl LogBuffer {
             if entries.len() > 10000 {
                 entries.drain(0..1000);
             }
+            self.version.fetch_add(1, Ordering::Relaxed);
         }
     }
 
+    /// Counter that changes whenever entries are added or cleared.
+    pub fn version(&self) -> u64 {
+        self.version.load(Ordering::Relaxed)
+    }
+
     pub fn get_all(&self) -> Vec<LogEntry> {
         self.entries
             .lock()
@@ -60,6 +100,7 @@ impl LogBuffer {
     pub fn clear(&self) {
         if let Ok(mut entries) = self.entries.lock() {
             entries.clear();
+            self.version.fetch_add(1, Ordering::Relaxed);
         }
     }
 }
@@ -79,38 +120,101 @@ where
     S: Subscriber,
 {
     fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
-        let metadata = event.metadata();
-        let level = *metadata.level();
-        let target = metadata.target();
+        self.buffer.push(entry_from_event(event));
+    }
+}
+
+/// Appends every log entry to a file as a line of JSON, as it happens, so
+/// the trace survives a crash or hang.
+pub struct LogFileLayer {
+    file: Mutex<File>,
+    // Masked in each message before it is written (`--redact-pattern`)
+    redact_patterns: Vec<Regex>,
+}
+
+impl LogFileLayer {
+    pub fn create(path: &Path) -> std::io::Result<Self> {
+        let file = File::create(path)?;
+        Ok(Self {
+            file: Mutex::new(file),
+            redact_patterns: Vec::new(),
+        })
+    }
 
-        // Create a visitor to extract the message
-        let mut visitor = MessageVisitor::default();
-        event.record(&mut visitor);
+    pub fn with_redact_patterns(mut self, patterns: Vec<Regex>) -> Self {
+        self.redact_patterns = patterns;
+        self
+    }
+}
 
-        let entry = LogEntry::new(level, target, visitor.message);
-        self.buffer.push(entry);
+impl<S> Layer<S> for LogFileLayer
+where
+    S: Subscriber,
+{
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut entry = entry_from_event(event);
+        if !self.redact_patterns.is_empty() {
+            entry.message = redact(entry.message, &self.redact_patterns);
+        }
+        let Ok(mut line) = serde_json::to_string(&entry) else {
+            return;
+        };
+        line.push('\n');
+        // Unbuffered, so each entry reaches the file immediately
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.write_all(line.as_bytes());
+        }
     }
 }
 
+fn entry_from_event(event: &Event<'_>) -> LogEntry {
+    let metadata = event.metadata();
+    let level = *metadata.level();
+    let target = metadata.target();
+
+    // Create a visitor to extract the message
+    let mut visitor = MessageVisitor::default();
+    event.record(&mut visitor);
+
+    let mut entry = LogEntry::new(level, target, visitor.message);
+    entry.request_id = visitor.request_id;
+    entry.method = visitor.method;
+    entry.action = visitor.action;
+    entry
+}
+
 #[derive(Default)]
 struct MessageVisitor {
     message: String,
+    request_id: Option<String>,
+    method: Option<String>,
+    action: Option<String>,
 }
 
 impl tracing::field::Visit for MessageVisitor {
     fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
-        if field.name() == "message" {
-            self.message = format!("{:?}", value);
-            // Remove quotes from debug formatting
-            if self.message.starts_with('"') && self.message.ends_with('"') {
-                self.message = self.message[1..self.message.len() - 1].to_string();
+        match field.name() {
+            "message" => {
+                self.message = format!("{:?}", value);
+                // Remove quotes from debug formatting
+                if self.message.starts_with('"') && self.message.ends_with('"') {
+                    self.message = self.message[1..self.message.len() - 1].to_string();
+                }
             }
+            "request_id" => self.request_id = Some(format!("{:?}", value)),
+            "method" => self.method = Some(format!("{:?}", value)),
+            "action" => self.action = Some(format!("{:?}", value)),
+            _ => {}
         }
     }
 
     fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
-        if field.name() == "message" {
-            self.message = value.to_string();
+        match field.name() {
+            "message" => self.message = value.to_string(),
+            "request_id" => self.request_id = Some(value.to_string()),
+            "method" => self.method = Some(value.to_string()),
+            "action" => self.action = Some(value.to_string()),
+            _ => {}
         }
     }
 }
@@ -5,20 +5,32 @@ mod tui;
 use anyhow::{Context, Result};
 use clap::Parser;
 use crossterm::{
+    clipboard::CopyToClipboard,
+    cursor,
     event::{
-        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind, KeyModifiers,
+        self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+        Event, KeyCode, KeyEventKind, KeyModifiers,
     },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use logging::{LogBuffer, LogBufferLayer};
-use mcp::McpClient;
-use ratatui::{backend::CrosstermBackend, Terminal};
+use logging::{LogBuffer, LogBufferLayer, LogFileLayer};
+use mcp::env::expand_env_vars;
+use mcp::protocol::{ClientCapabilities, Root};
+use mcp::{IdScheme, McpClient, DEFAULT_MAX_RESPONSE_BYTES};
+use ratatui::{backend::CrosstermBackend, Terminal, TerminalOptions, Viewport};
 use std::io;
-use tracing::Level;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{warn, Level};
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
-use tui::{render_ui, App};
+use tui::app::{glob_regex, InitialToolCall, LogExportFormat};
+use tui::cache::{ResultCache, CACHE_CAPACITY};
+use tui::settings::Settings;
+use tui::{render_ui, App, Tab};
 
 #[derive(Parser)]
 #[command(name = "mcpeek")]
@@ -32,17 +44,337 @@ struct Cli {
 
     #[arg(short, long, help = "Enable debug logging")]
     debug: bool,
+
+    #[arg(
+        long,
+        help = "Pass the command and arguments literally, without expanding ${VAR} references"
+    )]
+    no_expand: bool,
+
+    #[arg(
+        long,
+        help = "Run the command (and arguments) as a single string with `sh -c`, for pipelines and wrappers"
+    )]
+    shell: bool,
+
+    #[arg(long, help = "Call destructive tools without asking for confirmation")]
+    no_confirm: bool,
+
+    #[arg(
+        long = "confirm-pattern",
+        value_name = "PATTERN",
+        help = "Also ask for confirmation before calling tools whose name contains PATTERN (repeatable)"
+    )]
+    confirm_patterns: Vec<String>,
+
+    #[arg(
+        long,
+        help = "Send the startup list requests one at a time instead of as a JSON-RPC batch"
+    )]
+    no_batch: bool,
+
+    #[arg(
+        long,
+        visible_alias = "no-alt-screen",
+        help = "Draw the TUI inline below the prompt instead of on the alternate screen, leaving it in scrollback on exit"
+    )]
+    inline: bool,
+
+    #[arg(
+        long,
+        help = "Don't capture the mouse, leaving the terminal's own text selection working"
+    )]
+    no_mouse: bool,
+
+    #[arg(
+        long,
+        help = "Don't use colors, only bold, underline and reverse video (also set by NO_COLOR)"
+    )]
+    no_color: bool,
+
+    #[arg(
+        long = "redact-pattern",
+        value_name = "REGEX",
+        help = "Replace matches of REGEX with **** in tool results, resource reads and logs (repeatable)"
+    )]
+    redact_patterns: Vec<regex::Regex>,
+
+    #[arg(
+        long,
+        help = "Show results and logs unredacted, ignoring any --redact-pattern"
+    )]
+    no_redact: bool,
+
+    #[arg(
+        long,
+        help = "Reuse results of resource reads and read-only tool calls instead of re-fetching them"
+    )]
+    cache: bool,
+
+    #[arg(
+        long,
+        value_name = "JSON",
+        help = "JSON object to send as `_meta` in the initialize request params"
+    )]
+    init_meta: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "JSON",
+        help = "JSON object to send as the client `capabilities` in the initialize request (default: none)"
+    )]
+    capabilities: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "SCHEME",
+        default_value = "number",
+        help = "Request id format: number, string or uuid"
+    )]
+    id_scheme: IdScheme,
+
+    #[arg(
+        long,
+        value_name = "BYTES",
+        default_value_t = DEFAULT_MAX_RESPONSE_BYTES,
+        help = "Drop server messages longer than this, failing the request they answer (0 for no limit)"
+    )]
+    max_response_bytes: usize,
+
+    #[arg(
+        long,
+        value_name = "TABS",
+        value_delimiter = ',',
+        help = "Tabs to show, in order: tools, prompts, resources, info, logs, debug (needs --debug)"
+    )]
+    tabs: Option<Vec<Tab>>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Also write log entries to PATH as newline-delimited JSON, as they happen"
+    )]
+    log_file: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "SECS",
+        help = "Keep retrying to start and initialize the server for up to SECS seconds, for servers that are slow to come up"
+    )]
+    wait: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "BOOL",
+        num_args = 0..=1,
+        default_missing_value = "true",
+        help = "Show only names in the lists for this run, overriding the saved state"
+    )]
+    dense: Option<bool>,
+
+    #[arg(
+        long,
+        value_name = "BOOL",
+        num_args = 0..=1,
+        default_missing_value = "true",
+        help = "Show tools as a table for this run, overriding the saved state"
+    )]
+    tool_table: Option<bool>,
+
+    #[arg(
+        long,
+        value_name = "MS",
+        default_value_t = 0,
+        help = "Wait MS milliseconds after the initialize handshake before listing tools, prompts and resources"
+    )]
+    ready_delay: u64,
+
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        default_value = "pretty",
+        help = "Format of logs saved with S: pretty, compact or jsonl (one JSON object per line)"
+    )]
+    log_export_format: LogExportFormat,
+
+    #[arg(
+        long = "root",
+        value_name = "DIR",
+        value_parser = parse_root,
+        help = "Directory the server may access, shown in Server Info (repeatable)"
+    )]
+    roots: Vec<Root>,
+
+    #[arg(
+        long = "select",
+        value_name = "GLOB",
+        value_parser = parse_select,
+        help = "Only show tools whose names match GLOB, e.g. 'github_*' (repeatable)"
+    )]
+    tool_select: Vec<regex::Regex>,
+
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "Open the call form for tool NAME once the tools are listed (it isn't run until you press Enter)"
+    )]
+    tool: Option<String>,
+
+    #[arg(
+        long = "arg",
+        value_name = "KEY=VALUE",
+        requires = "tool",
+        value_parser = parse_key_value,
+        help = "Prefill parameter KEY of the --tool form with VALUE (repeatable)"
+    )]
+    tool_args: Vec<(String, String)>,
+
+    #[arg(
+        long,
+        requires = "tool",
+        help = "Run the --tool call as soon as its form is filled and show the result"
+    )]
+    once: bool,
+
+    #[arg(
+        long,
+        value_name = "COLS",
+        help = "Wrap the detail view and logs in a centered column at most COLS wide (0: the full width), overriding the saved state"
+    )]
+    wrap_width: Option<u16>,
+
+    #[arg(
+        long,
+        help = "Don't restore view preferences from the state file or save them on exit"
+    )]
+    no_state: bool,
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    let cli = Cli::parse();
+/// Parse a `--arg KEY=VALUE` pair.
+fn parse_key_value(s: &str) -> std::result::Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(format!("expected KEY=VALUE, got '{}'", s)),
+    }
+}
+
+/// Parse a `--select` glob into a regex matching whole tool names.
+fn parse_select(s: &str) -> std::result::Result<regex::Regex, String> {
+    glob_regex(s).map_err(|e| e.to_string())
+}
+
+/// Parse a `--root` directory into a `file://` root named after it.
+fn parse_root(s: &str) -> std::result::Result<Root, String> {
+    let path = std::fs::canonicalize(s).map_err(|e| format!("{}: {}", s, e))?;
+    if !path.is_dir() {
+        return Err(format!("{} is not a directory", s));
+    }
+    Ok(Root {
+        uri: format!("file://{}", path.display()),
+        name: path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned()),
+    })
+}
+
+/// Rows used by the TUI in `--inline` mode (fewer if the terminal is smaller).
+const INLINE_HEIGHT: u16 = 30;
+
+/// How often to redraw while a request's spinner is showing.
+const SPINNER_REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How often to redraw when nothing has changed.
+const IDLE_REDRAW_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Pause between connection attempts with `--wait`.
+const WAIT_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Exit code for bad arguments and other errors.
+const EXIT_USAGE: u8 = 1;
+
+/// Exit code when the server can't be started or fails to initialize.
+const EXIT_CONNECTION: u8 = 2;
+
+/// Exit code when a request to the server times out.
+const EXIT_TIMEOUT: u8 = 4;
+
+/// An error together with the process exit code it maps to.
+struct Failure {
+    code: u8,
+    error: anyhow::Error,
+}
+
+impl From<anyhow::Error> for Failure {
+    fn from(error: anyhow::Error) -> Self {
+        Failure {
+            code: EXIT_USAGE,
+            error,
+        }
+    }
+}
 
+impl Failure {
+    /// Map a connection error, telling timeouts apart from other failures.
+    fn connection(error: anyhow::Error) -> Self {
+        let timed_out = error
+            .chain()
+            .any(|cause| cause.is::<tokio::time::error::Elapsed>());
+        Failure {
+            code: if timed_out {
+                EXIT_TIMEOUT
+            } else {
+                EXIT_CONNECTION
+            },
+            error,
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        Err(err) => {
+            // --help and --version go to stdout and aren't errors
+            let _ = err.print();
+            return if err.use_stderr() {
+                ExitCode::from(EXIT_USAGE)
+            } else {
+                ExitCode::SUCCESS
+            };
+        }
+    };
+
+    match run(cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(failure) => {
+            eprintln!("Error: {:?}", failure.error);
+            ExitCode::from(failure.code)
+        }
+    }
+}
+
+#[tokio::main]
+async fn run(cli: Cli) -> Result<(), Failure> {
     let log_level = if cli.debug { Level::DEBUG } else { Level::INFO };
 
     // Create custom log buffer to capture logs in memory
     let log_buffer = LogBuffer::new();
     let log_buffer_layer = LogBufferLayer::new(log_buffer.clone());
+    let log_file_layer = cli
+        .log_file
+        .as_deref()
+        .map(|path| {
+            LogFileLayer::create(path)
+                .with_context(|| format!("Failed to create log file {}", path.display()))
+        })
+        .transpose()?
+        .map(|layer| {
+            if cli.no_redact {
+                layer
+            } else {
+                layer.with_redact_patterns(cli.redact_patterns.clone())
+            }
+        });
 
     // Initialize tracing with custom layer instead of stderr
     tracing_subscriber::registry()
@@ -50,76 +382,417 @@ async fn main() -> Result<()> {
             log_level,
         ))
         .with(log_buffer_layer)
+        .with(log_file_layer)
         .init();
 
-    run_tui(&cli.command, &cli.args, log_buffer, cli.debug).await?;
+    let (command, args) = if cli.shell {
+        // The shell does its own ${VAR} expansion
+        let script = std::iter::once(cli.command.as_str())
+            .chain(cli.args.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(" ");
+        ("sh".to_string(), vec!["-c".to_string(), script])
+    } else if cli.no_expand {
+        (cli.command.clone(), cli.args.clone())
+    } else {
+        let command = expand_env_vars(&cli.command)?;
+        let args = cli
+            .args
+            .iter()
+            .map(|arg| expand_env_vars(arg))
+            .collect::<Result<Vec<_>>>()?;
+        (command, args)
+    };
+
+    check_tool_tab(&cli)?;
+    let init_meta = cli.init_meta.as_deref().map(parse_init_meta).transpose()?;
+    let capabilities = cli
+        .capabilities
+        .as_deref()
+        .map(parse_capabilities)
+        .transpose()?
+        .unwrap_or_default();
 
+    // Connect before taking over the terminal, so startup errors are printed
+    // on the normal screen rather than lost with the alternate one
+    let connection = match cli.wait {
+        Some(secs) => {
+            let wait = Duration::from_secs(secs);
+            connect_with_wait(&command, &args, init_meta, &capabilities, &cli, wait).await
+        }
+        None => connect(&command, &args, init_meta, &capabilities, &cli).await,
+    };
+    let client = connection.map_err(Failure::connection)?;
+
+    run_tui(client, &command, &args, log_buffer, &cli).await?;
+
+    Ok(())
+}
+
+/// `--tool` opens its form in the Tools tab, so `--tabs` can't leave it out.
+fn check_tool_tab(cli: &Cli) -> Result<()> {
+    let tools_hidden = cli
+        .tabs
+        .as_ref()
+        .is_some_and(|tabs| !tabs.contains(&Tab::Tools));
+    if cli.tool.is_some() && tools_hidden {
+        anyhow::bail!("--tool needs the Tools tab, which --tabs hides");
+    }
     Ok(())
 }
 
+/// Parse the `--init-meta` value, which must be a JSON object.
+fn parse_init_meta(raw: &str) -> Result<serde_json::Value> {
+    let meta: serde_json::Value =
+        serde_json::from_str(raw).context("--init-meta is not valid JSON")?;
+    if !meta.is_object() {
+        anyhow::bail!("--init-meta must be a JSON object");
+    }
+    Ok(meta)
+}
+
+fn parse_capabilities(raw: &str) -> Result<ClientCapabilities> {
+    let capabilities: serde_json::Value =
+        serde_json::from_str(raw).context("--capabilities is not valid JSON")?;
+    if !capabilities.is_object() {
+        anyhow::bail!("--capabilities must be a JSON object");
+    }
+    serde_json::from_value(capabilities).context("Invalid --capabilities")
+}
+
+/// Start the server and run the initialize handshake.
+async fn connect(
+    command: &str,
+    args: &[String],
+    init_meta: Option<serde_json::Value>,
+    capabilities: &ClientCapabilities,
+    cli: &Cli,
+) -> Result<Arc<McpClient>> {
+    let client = Arc::new(
+        McpClient::new(command, args)
+            .await
+            .context("Failed to create MCP client")?
+            .with_init_meta(init_meta)
+            .with_capabilities(capabilities.clone())
+            .with_id_scheme(cli.id_scheme)
+            .with_max_response_bytes(cli.max_response_bytes)
+            .with_roots(cli.roots.clone()),
+    );
+
+    client
+        .initialize()
+        .await
+        .context("Failed to initialize MCP client")?;
+
+    Ok(client)
+}
+
+/// Retry `connect` until it succeeds or `wait` runs out, for servers that
+/// are still starting up.
+async fn connect_with_wait(
+    command: &str,
+    args: &[String],
+    init_meta: Option<serde_json::Value>,
+    capabilities: &ClientCapabilities,
+    cli: &Cli,
+    wait: Duration,
+) -> Result<Arc<McpClient>> {
+    let deadline = Instant::now() + wait;
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let attempt = connect(command, args, init_meta.clone(), capabilities, cli);
+        match tokio::time::timeout(remaining, attempt).await {
+            Ok(Ok(client)) => return Ok(client),
+            Ok(Err(e)) if Instant::now() + WAIT_RETRY_INTERVAL < deadline => {
+                eprintln!("Waiting for server... ({:#})", e);
+                tokio::time::sleep(WAIT_RETRY_INTERVAL).await;
+            }
+            Ok(Err(e)) => return Err(e),
+            // The server never came up, which is a connection failure rather
+            // than a request timing out
+            Err(_) => anyhow::bail!("Server not ready after {}s", wait.as_secs()),
+        }
+    }
+}
+
 async fn run_tui(
+    client: Arc<McpClient>,
     command: &str,
     args: &[String],
     log_buffer: LogBuffer,
-    debug_mode: bool,
+    cli: &Cli,
 ) -> Result<()> {
+    let mode = TerminalMode {
+        inline: cli.inline,
+        mouse: !cli.no_mouse,
+    };
+    install_panic_hook(mode);
     enable_raw_mode()?;
+    // Restores the terminal however we leave this function, including `?`
+    let guard = TerminalGuard { mode };
+
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    if !cli.inline {
+        execute!(stdout, EnterAlternateScreen)?;
+    }
+    if mode.mouse {
+        execute!(stdout, EnableMouseCapture)?;
+    }
+    execute!(stdout, EnableBracketedPaste)?;
     let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+    let viewport = if cli.inline {
+        Viewport::Inline(INLINE_HEIGHT)
+    } else {
+        Viewport::Fullscreen
+    };
+    let mut terminal = Terminal::with_options(backend, TerminalOptions { viewport })?;
 
-    let client = McpClient::new(command, args)
-        .await
-        .context("Failed to create MCP client")?;
-
-    client
-        .initialize()
-        .await
-        .context("Failed to initialize MCP client")?;
-
-    let mut app = App::new(debug_mode);
+    let mut app = App::new(cli.debug);
+    let state_path = if cli.no_state {
+        None
+    } else {
+        Settings::default_path()
+    };
+    let saved = match &state_path {
+        Some(path) => Settings::load(path).unwrap_or_else(|e| {
+            warn!("Ignoring saved state: {:#}", e);
+            Settings::default()
+        }),
+        None => Settings::default(),
+    };
+    app.settings = saved.clone();
+    if let Some(dense) = cli.dense {
+        app.settings.dense_lists = dense;
+    }
+    if let Some(tool_table) = cli.tool_table {
+        app.settings.tool_table = tool_table;
+    }
+    if let Some(width) = cli.wrap_width {
+        app.settings.wrap_width = (width > 0).then_some(width);
+    }
+    if let Some(width) = app.settings.wrap_width.or(saved.wrap_width) {
+        app.last_wrap_width = width;
+    }
+    let initial = app.settings.clone();
+    if let Some(tabs) = &cli.tabs {
+        app.set_tabs(tabs);
+    }
+    app.server_info = client.get_server_info().await;
+    app.last_loaded.insert(Tab::ServerInfo, app.connected_at);
+    app.connection_target = std::iter::once(command)
+        .chain(args.iter().map(String::as_str))
+        .collect::<Vec<_>>()
+        .join(" ");
+    app.client_params = Some(client.initialize_params());
+    app.roots = client.roots().to_vec();
+    app.confirm_destructive = !cli.no_confirm;
+    app.confirm_patterns = cli.confirm_patterns.clone();
+    app.batch_requests = !cli.no_batch;
+    app.log_export_format = cli.log_export_format;
+    app.tool_select = cli.tool_select.clone();
+    app.ready_delay = Duration::from_millis(cli.ready_delay);
+    // https://no-color.org: any non-empty value turns colors off
+    app.no_color = cli.no_color || std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    if !cli.no_redact {
+        app.redact_patterns = cli.redact_patterns.clone();
+    }
+    if cli.cache {
+        app.result_cache = Some(ResultCache::new(CACHE_CAPACITY));
+    }
+    if let Some(tool_name) = &cli.tool {
+        app.initial_tool_call = Some(InitialToolCall {
+            tool_name: tool_name.clone(),
+            arguments: cli.tool_args.clone(),
+            execute: cli.once,
+        });
+    }
+    app.preload_lists(&client);
     let res = run_tui_loop(&mut terminal, &mut app, &client, log_buffer).await;
 
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
+    if cli.inline {
+        // Leave the last frame in scrollback and put the prompt below it
+        let bottom = terminal.get_frame().area().bottom();
+        terminal.set_cursor_position((0, bottom.saturating_sub(1)))?;
+    }
+    drop(guard);
+    if cli.inline {
+        println!();
+    }
+
+    if let Some(path) = &state_path {
+        let mut settings = app.settings.clone();
+        // A flag only applies to this run unless it was toggled in the UI
+        if cli.dense.is_some() && settings.dense_lists == initial.dense_lists {
+            settings.dense_lists = saved.dense_lists;
+        }
+        if cli.tool_table.is_some() && settings.tool_table == initial.tool_table {
+            settings.tool_table = saved.tool_table;
+        }
+        if cli.wrap_width.is_some() && settings.wrap_width == initial.wrap_width {
+            settings.wrap_width = saved.wrap_width;
+        }
+        if settings != saved {
+            if let Err(e) = settings.save(path) {
+                eprintln!("Warning: failed to save state: {:#}", e);
+            }
+        }
+    }
 
     client.shutdown().await?;
 
     res
 }
 
+/// Which optional terminal features `run_tui` switched on.
+#[derive(Clone, Copy)]
+struct TerminalMode {
+    inline: bool,
+    mouse: bool,
+}
+
+/// Restores the terminal when dropped, so early returns leave it usable.
+struct TerminalGuard {
+    mode: TerminalMode,
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore_terminal(self.mode);
+    }
+}
+
+/// Undo the raw mode and screen setup from `run_tui`. Best effort, and safe
+/// to call more than once.
+fn restore_terminal(mode: TerminalMode) {
+    let _ = disable_raw_mode();
+    let mut stdout = io::stdout();
+    if !mode.inline {
+        let _ = execute!(stdout, LeaveAlternateScreen);
+    }
+    if mode.mouse {
+        let _ = execute!(stdout, DisableMouseCapture);
+    }
+    let _ = execute!(stdout, DisableBracketedPaste, cursor::Show);
+}
+
+/// Restore the terminal before the panic message prints, so it isn't lost
+/// on the alternate screen or mangled by raw mode.
+fn install_panic_hook(mode: TerminalMode) {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        // Panics in background tasks are caught by tokio and the TUI keeps
+        // running, so only restore the terminal when the UI thread panics
+        if std::thread::current().name() == Some("main") {
+            restore_terminal(mode);
+        }
+        default_hook(info);
+    }));
+}
+
 async fn run_tui_loop(
     terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
     app: &mut App,
-    client: &McpClient,
+    client: &Arc<McpClient>,
     log_buffer: LogBuffer,
 ) -> Result<()> {
-    app.load_data(client).await?;
+    // Only redraw when something changed, apart from a slow idle refresh and
+    // keeping the spinner moving while a request is in flight
+    let mut dirty = true;
+    let mut last_draw = Instant::now();
+    let mut debug_log_version = None;
 
     loop {
         // Update logs in the background
-        app.update_logs(client).await;
+        dirty |= app.update_logs(client).await;
+        dirty |= app.update_connection(client.is_closed());
 
-        // Update debug logs from buffer
-        app.update_debug_logs(log_buffer.get_all());
+        // Update debug logs from buffer, copying it only when it has changed
+        let version = log_buffer.version();
+        if debug_log_version != Some(version) {
+            debug_log_version = Some(version);
+            app.update_debug_logs(log_buffer.get_all());
+            dirty = true;
+        }
 
-        terminal.draw(|f| render_ui(f, app))?;
+        // Pick up results of background tool calls, prompt gets and reads
+        dirty |= app.poll_tasks();
+        dirty |= app.run_initial_tool_call(client);
 
-        if event::poll(std::time::Duration::from_millis(100))? {
-            if let Event::Key(key) = event::read()? {
-                if key.kind == KeyEventKind::Press {
-                    if app.tool_call_input_mode {
+        let since_draw = last_draw.elapsed();
+        if dirty
+            || (app.is_busy() && since_draw >= SPINNER_REDRAW_INTERVAL)
+            || since_draw >= IDLE_REDRAW_INTERVAL
+        {
+            terminal.draw(|f| render_ui(f, app))?;
+            dirty = false;
+            last_draw = Instant::now();
+        }
+
+        if event::poll(Duration::from_millis(100))? {
+            dirty = true;
+            match event::read()? {
+                Event::Paste(text) => app.paste_input(&text),
+                Event::Resize(width, height) => {
+                    app.handle_resize(width, height);
+                    // Pick up the new size now rather than on the next draw
+                    terminal.autoresize()?;
+                }
+                Event::Key(key) if key.kind == KeyEventKind::Press => {
+                    if app.show_help {
+                        match key.code {
+                            KeyCode::Down => app.help_scroll = app.help_scroll.saturating_add(1),
+                            KeyCode::Up => app.help_scroll = app.help_scroll.saturating_sub(1),
+                            _ => {
+                                app.show_help = false;
+                                app.help_scroll = 0;
+                            }
+                        }
+                    } else if app.is_busy() && key.code == KeyCode::Esc {
+                        app.cancel_task();
+                    } else if app.tool_confirmation.is_some() {
+                        match key.code {
+                            KeyCode::Char('y') | KeyCode::Char('Y') => {
+                                app.confirm_tool_call(client);
+                            }
+                            KeyCode::Esc | KeyCode::Char('n') | KeyCode::Char('N') => {
+                                app.cancel_tool_confirmation();
+                            }
+                            _ => {}
+                        }
+                    } else if app.text_prompt.is_some() {
+                        match key.code {
+                            KeyCode::Esc => app.cancel_text_prompt(),
+                            KeyCode::Enter => app.submit_text_prompt(client),
+                            KeyCode::Char('w') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                                app.delete_word_before_cursor();
+                            }
+                            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                                app.clear_current_input();
+                            }
+                            KeyCode::Backspace => app.delete_current_input(),
+                            KeyCode::Delete => app.delete_input_forward(),
+                            KeyCode::Left => app.move_cursor_left(),
+                            KeyCode::Right => app.move_cursor_right(),
+                            KeyCode::Home => app.move_cursor_home(),
+                            KeyCode::End => app.move_cursor_end(),
+                            KeyCode::Char(c) => app.update_current_input(c),
+                            _ => {}
+                        }
+                    } else if app.tool_call_input_mode {
                         // Handle tool call input mode
                         match key.code {
                             KeyCode::Esc => app.cancel_tool_call(),
+                            KeyCode::Char('j') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                                app.toggle_raw_json_mode();
+                            }
+                            KeyCode::Enter
+                                if app.raw_json_mode
+                                    && key.modifiers.contains(KeyModifiers::ALT) =>
+                            {
+                                app.update_current_input('\n');
+                            }
                             KeyCode::Enter => {
-                                app.execute_tool_call(client).await;
+                                app.execute_tool_call(client);
                             }
                             KeyCode::Tab => {
                                 if key.modifiers.contains(KeyModifiers::SHIFT) {
@@ -129,7 +802,18 @@ async fn run_tui_loop(
                                 }
                             }
                             KeyCode::BackTab => app.previous_input_field(),
+                            KeyCode::Char('w') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                                app.delete_word_before_cursor();
+                            }
+                            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                                app.clear_current_input();
+                            }
                             KeyCode::Backspace => app.delete_current_input(),
+                            KeyCode::Delete => app.delete_input_forward(),
+                            KeyCode::Left => app.move_cursor_left(),
+                            KeyCode::Right => app.move_cursor_right(),
+                            KeyCode::Home => app.move_cursor_home(),
+                            KeyCode::End => app.move_cursor_end(),
                             KeyCode::Up => app.scroll_tool_input_up(),
                             KeyCode::Down => app.scroll_tool_input_down(),
                             KeyCode::Char(c) => app.update_current_input(c),
@@ -140,7 +824,7 @@ async fn run_tui_loop(
                         match key.code {
                             KeyCode::Esc => app.cancel_prompt_input(),
                             KeyCode::Enter => {
-                                app.execute_prompt_get(client).await;
+                                app.execute_prompt_get(client);
                             }
                             KeyCode::Tab => {
                                 if key.modifiers.contains(KeyModifiers::SHIFT) {
@@ -150,7 +834,18 @@ async fn run_tui_loop(
                                 }
                             }
                             KeyCode::BackTab => app.previous_input_field(),
+                            KeyCode::Char('w') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                                app.delete_word_before_cursor();
+                            }
+                            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                                app.clear_current_input();
+                            }
                             KeyCode::Backspace => app.delete_current_input(),
+                            KeyCode::Delete => app.delete_input_forward(),
+                            KeyCode::Left => app.move_cursor_left(),
+                            KeyCode::Right => app.move_cursor_right(),
+                            KeyCode::Home => app.move_cursor_home(),
+                            KeyCode::End => app.move_cursor_end(),
                             KeyCode::Up => app.scroll_tool_input_up(),
                             KeyCode::Down => app.scroll_tool_input_down(),
                             KeyCode::Char(c) => app.update_current_input(c),
@@ -159,13 +854,37 @@ async fn run_tui_loop(
                     } else if app.detail_view.is_some() {
                         match key.code {
                             KeyCode::Esc => app.close_detail(),
+                            KeyCode::Char('?') => app.show_help = true,
                             KeyCode::Char('q') | KeyCode::Char('Q') => app.quit(),
+                            KeyCode::Char(c @ '1'..='9') => {
+                                app.read_embedded_resource(client, c as usize - '1' as usize);
+                            }
+                            KeyCode::Char('a') | KeyCode::Char('A') => {
+                                app.use_prompt_json_as_tool_arguments();
+                            }
+                            KeyCode::Char('j') | KeyCode::Char('J') => app.toggle_item_json(),
+                            KeyCode::Char('l') | KeyCode::Char('L') => app.toggle_wrap_width(),
                             KeyCode::Char('c') | KeyCode::Char('C') => match app.current_tab {
                                 tui::Tab::Tools => app.start_tool_call(),
                                 tui::Tab::Prompts => app.start_prompt_get(),
-                                tui::Tab::Resources => app.read_resource(client).await,
+                                tui::Tab::Resources => app.read_resource(client),
                                 _ => {}
                             },
+                            KeyCode::Char('r') | KeyCode::Char('R') => app.refresh_cached(client),
+                            KeyCode::Char('x') | KeyCode::Char('X') => app.toggle_hex_dump(),
+                            KeyCode::Char('n') | KeyCode::Char('N') => app.jump_to_section(true),
+                            KeyCode::Char('p') | KeyCode::Char('P') => app.jump_to_section(false),
+                            KeyCode::Char('z') | KeyCode::Char('Z') => app.toggle_section(),
+                            KeyCode::Char('v') | KeyCode::Char('V') => app.toggle_all_sections(),
+                            KeyCode::Char('f') | KeyCode::Char('F') => app.show_full_detail(),
+                            KeyCode::Char('w') | KeyCode::Char('W') => match app.save_detail() {
+                                Ok(filename) => {
+                                    app.error_message = Some(format!("✓ Saved to: {}", filename));
+                                }
+                                Err(e) => {
+                                    app.error_message = Some(format!("Failed to save: {}", e));
+                                }
+                            },
                             KeyCode::Down => app.next_item(),
                             KeyCode::Up => app.previous_item(),
                             KeyCode::PageDown => app.page_down(),
@@ -175,27 +894,28 @@ async fn run_tui_loop(
                     } else {
                         match key.code {
                             KeyCode::Char('q') | KeyCode::Char('Q') => app.quit(),
+                            KeyCode::Char('?') => app.show_help = true,
                             KeyCode::Char('c') | KeyCode::Char('C') => match app.current_tab {
                                 tui::Tab::Tools => app.start_tool_call(),
                                 tui::Tab::Prompts => app.start_prompt_get(),
-                                tui::Tab::Resources => app.read_resource(client).await,
+                                tui::Tab::Resources => app.read_resource(client),
                                 _ => {}
                             },
                             KeyCode::Tab => {
-                                app.current_tab = app.current_tab.next(app.debug_mode);
-                                app.load_data(client).await?;
+                                app.next_tab();
+                                app.load_data(client);
                             }
                             KeyCode::BackTab => {
-                                app.current_tab = app.current_tab.previous(app.debug_mode);
-                                app.load_data(client).await?;
+                                app.previous_tab();
+                                app.load_data(client);
                             }
                             KeyCode::Left => {
-                                app.current_tab = app.current_tab.previous(app.debug_mode);
-                                app.load_data(client).await?;
+                                app.previous_tab();
+                                app.load_data(client);
                             }
                             KeyCode::Right => {
-                                app.current_tab = app.current_tab.next(app.debug_mode);
-                                app.load_data(client).await?;
+                                app.next_tab();
+                                app.load_data(client);
                             }
                             KeyCode::Down => app.next_item(),
                             KeyCode::Up => app.previous_item(),
@@ -203,25 +923,98 @@ async fn run_tui_loop(
                             KeyCode::PageUp => app.page_up(),
                             KeyCode::Enter => app.show_detail(),
                             KeyCode::Char('r') | KeyCode::Char('R') => {
-                                app.load_data(client).await?;
+                                app.load_data(client);
                             }
                             KeyCode::Char('e') | KeyCode::Char('E') => {
                                 app.scroll_to_bottom();
                             }
-                            KeyCode::Char('s') | KeyCode::Char('S') => {
-                                // Save logs when on ServerLogs or DebugLogs tab
+                            KeyCode::Char('l') | KeyCode::Char('L')
+                                if app.current_tab == tui::Tab::ServerLogs
+                                    || app.current_tab == tui::Tab::DebugLogs =>
+                            {
+                                app.toggle_wrap_width();
+                            }
+                            KeyCode::Char('t') | KeyCode::Char('T')
                                 if app.current_tab == tui::Tab::ServerLogs
-                                    || app.current_tab == tui::Tab::DebugLogs
-                                {
-                                    match app.export_logs() {
-                                        Ok(filename) => {
-                                            app.error_message =
-                                                Some(format!("✓ Logs saved to: {}", filename));
-                                        }
-                                        Err(e) => {
-                                            app.error_message =
-                                                Some(format!("Failed to save logs: {}", e));
-                                        }
+                                    || app.current_tab == tui::Tab::DebugLogs =>
+                            {
+                                app.toggle_log_follow();
+                            }
+                            KeyCode::Char('n') | KeyCode::Char('N') => {
+                                app.jump_to_problem(true);
+                            }
+                            KeyCode::Char('p') | KeyCode::Char('P') => {
+                                app.jump_to_problem(false);
+                            }
+                            KeyCode::Char('f') | KeyCode::Char('F') => {
+                                app.start_debug_log_filter();
+                            }
+                            KeyCode::Char('u') | KeyCode::Char('U') => {
+                                app.start_resource_uri_prompt();
+                            }
+                            KeyCode::Char('y') | KeyCode::Char('Y') => {
+                                if let Some(id) = app.selected_identifier().map(str::to_string) {
+                                    // OSC 52, which the terminal (or multiplexer) must allow
+                                    let copied = execute!(
+                                        terminal.backend_mut(),
+                                        CopyToClipboard::to_clipboard_from(&id)
+                                    );
+                                    app.error_message = Some(match copied {
+                                        Ok(()) => format!("✓ Copied {}", id),
+                                        Err(e) => format!("Failed to copy: {}", e),
+                                    });
+                                }
+                            }
+                            KeyCode::Char('d') | KeyCode::Char('D') => {
+                                app.settings.dense_lists = !app.settings.dense_lists;
+                            }
+                            KeyCode::Char('t') | KeyCode::Char('T')
+                                if app.current_tab == tui::Tab::Tools =>
+                            {
+                                app.settings.tool_table = !app.settings.tool_table;
+                            }
+                            KeyCode::Char('o') | KeyCode::Char('O')
+                                if app.current_tab == tui::Tab::Tools =>
+                            {
+                                app.cycle_tool_sort();
+                            }
+                            KeyCode::Char('m') | KeyCode::Char('M')
+                                if app.current_tab == tui::Tab::Tools =>
+                            {
+                                app.toggle_recent_tools();
+                            }
+                            KeyCode::Char('i') | KeyCode::Char('I')
+                                if app.current_tab == tui::Tab::ServerInfo =>
+                            {
+                                app.reinitialize(client);
+                            }
+                            KeyCode::Char('s') | KeyCode::Char('S')
+                                if app.current_tab == tui::Tab::ServerLogs
+                                    || app.current_tab == tui::Tab::DebugLogs =>
+                            {
+                                // Save logs when on ServerLogs or DebugLogs tab
+                                match app.export_logs() {
+                                    Ok(filename) => {
+                                        app.error_message =
+                                            Some(format!("✓ Logs saved to: {}", filename));
+                                    }
+                                    Err(e) => {
+                                        app.error_message =
+                                            Some(format!("Failed to save logs: {}", e));
+                                    }
+                                }
+                            }
+                            KeyCode::Char('s') | KeyCode::Char('S')
+                                if app.current_tab == tui::Tab::ServerInfo =>
+                            {
+                                match app.export_server_info() {
+                                    Ok(filename) => {
+                                        app.error_message =
+                                            Some(format!("✓ Server info saved to: {}", filename));
+                                    }
+                                    Err(e) => {
+                                        app.error_message =
+                                            Some(format!("Failed to save server info: {}", e));
                                     }
                                 }
                             }
@@ -229,6 +1022,7 @@ async fn run_tui_loop(
                         }
                     }
                 }
+                _ => {}
             }
         }
 
@@ -239,3 +1033,62 @@ async fn run_tui_loop(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_connection_failure_exit_codes() {
+        let elapsed = tokio::time::timeout(Duration::ZERO, std::future::pending::<()>())
+            .await
+            .unwrap_err();
+        let error = anyhow::Error::new(elapsed).context("Failed to initialize MCP client");
+        assert_eq!(Failure::connection(error).code, EXIT_TIMEOUT);
+
+        let error = anyhow::anyhow!("Command 'nope' not found in PATH");
+        assert_eq!(Failure::connection(error).code, EXIT_CONNECTION);
+    }
+
+    #[tokio::test]
+    async fn test_wait_deadline_is_a_connection_failure() {
+        // Starts, but never answers the initialize request
+        let cli = Cli::parse_from(["mcpeek", "sleep", "10"]);
+        let error = connect_with_wait(
+            &cli.command,
+            &cli.args,
+            None,
+            &ClientCapabilities::default(),
+            &cli,
+            Duration::from_secs(1),
+        )
+        .await
+        .err()
+        .unwrap();
+        assert_eq!(error.to_string(), "Server not ready after 1s");
+        assert_eq!(Failure::connection(error).code, EXIT_CONNECTION);
+    }
+
+    #[test]
+    fn test_tool_needs_tools_tab() {
+        let cli = Cli::parse_from([
+            "mcpeek",
+            "--tool",
+            "search",
+            "--tabs",
+            "prompts,logs",
+            "srv",
+        ]);
+        let error = check_tool_tab(&cli).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "--tool needs the Tools tab, which --tabs hides"
+        );
+        assert_eq!(Failure::from(error).code, EXIT_USAGE);
+
+        let cli = Cli::parse_from(["mcpeek", "--tool", "search", "--tabs", "logs,tools", "srv"]);
+        assert!(check_tool_tab(&cli).is_ok());
+        let cli = Cli::parse_from(["mcpeek", "--tabs", "prompts", "srv"]);
+        assert!(check_tool_tab(&cli).is_ok());
+    }
+}
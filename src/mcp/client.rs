@@ -3,23 +3,135 @@ use anyhow::{Context, Result};
 use serde_json::Value;
 use std::collections::HashMap;
 use std::process::Stdio;
-use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicUsize, Ordering};
 use std::sync::Arc;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::process::{Child, ChildStderr, ChildStdin, ChildStdout, Command};
-use tokio::sync::{mpsc, oneshot, Mutex};
+use tokio::sync::{mpsc, oneshot, watch, Mutex};
 use tracing::{debug, error, warn};
 
+/// How long to wait for a whole batch before falling back to individual
+/// requests; servers that don't support batching may never answer it.
+const BATCH_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// How long `initialize` still waits for a response after the server has
+/// written something other than JSON-RPC to stdout, in case it was only a
+/// banner.
+const STARTUP_NOISE_GRACE: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Error for requests the server can no longer answer because its stdout
+/// closed, usually because it exited.
+const SERVER_CLOSED: &str = "Server closed its stdout before responding (has it exited?)";
+
+/// Error for a request that couldn't be written because the server's stdin
+/// closed.
+const SERVER_STDIN_CLOSED: &str = "Server stopped reading its stdin (has it exited?)";
+
+/// Longest message the server may write on one line before it is dropped
+/// rather than buffered (`--max-response-bytes`).
+pub const DEFAULT_MAX_RESPONSE_BYTES: usize = 64 * 1024 * 1024;
+
+/// How much of each end of a dropped message is kept to find its id.
+const DROPPED_PEEK_BYTES: usize = 1024;
+
+type PendingRequests = Arc<Mutex<HashMap<RequestId, oneshot::Sender<JsonRpcResponse>>>>;
+type BatchRejection = Arc<Mutex<Option<oneshot::Sender<JsonRpcError>>>>;
+
+/// How request ids are generated. Some servers expect string ids.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IdScheme {
+    /// Increasing integers: 1, 2, 3, ...
+    #[default]
+    Number,
+    /// Increasing strings: "mcpeek-1", "mcpeek-2", ...
+    String,
+    /// Random UUIDs
+    Uuid,
+}
+
+impl std::str::FromStr for IdScheme {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "number" => Ok(IdScheme::Number),
+            "string" => Ok(IdScheme::String),
+            "uuid" => Ok(IdScheme::Uuid),
+            _ => Err(format!(
+                "unknown id scheme '{}' (expected number, string or uuid)",
+                s
+            )),
+        }
+    }
+}
+
+/// Results of listing tools, prompts and resources together. A list is
+/// `None` when the server doesn't advertise that capability.
+pub struct ServerLists {
+    pub tools: Option<Result<Vec<Tool>>>,
+    pub prompts: Option<Result<Vec<Prompt>>>,
+    pub resources: Option<Result<Vec<Resource>>>,
+}
+
+impl ServerLists {
+    /// Whether any advertised list failed or came back empty, as it may from
+    /// a server that isn't ready yet.
+    pub fn has_missing(&self) -> bool {
+        is_missing(&self.tools) || is_missing(&self.prompts) || is_missing(&self.resources)
+    }
+
+    /// Take lists from a retry where this attempt failed, or came back empty
+    /// and the retry succeeded.
+    pub fn fill_from(&mut self, retry: ServerLists) {
+        fill(&mut self.tools, retry.tools);
+        fill(&mut self.prompts, retry.prompts);
+        fill(&mut self.resources, retry.resources);
+    }
+}
+
+fn is_missing<T>(list: &Option<Result<Vec<T>>>) -> bool {
+    matches!(list, Some(Err(_))) || matches!(list, Some(Ok(items)) if items.is_empty())
+}
+
+fn fill<T>(list: &mut Option<Result<Vec<T>>>, retry: Option<Result<Vec<T>>>) {
+    let replace = match (&*list, &retry) {
+        (Some(Err(_)), Some(_)) => true,
+        (Some(Ok(items)), Some(Ok(_))) => items.is_empty(),
+        _ => false,
+    };
+    if replace {
+        *list = retry;
+    }
+}
+
 pub struct McpClient {
     child: Arc<Mutex<Child>>,
     stdin: Arc<Mutex<ChildStdin>>,
     request_id: AtomicI64,
+    id_scheme: IdScheme,
     #[allow(dead_code)]
     response_tx: mpsc::UnboundedSender<ResponseMessage>,
     #[allow(dead_code)]
     response_rx: Arc<Mutex<mpsc::UnboundedReceiver<ResponseMessage>>>,
-    pending_requests: Arc<Mutex<HashMap<i64, oneshot::Sender<JsonRpcResponse>>>>,
+    pending_requests: PendingRequests,
+    // Set while a batch is in flight, to hear about the server rejecting it
+    batch_rejection: BatchRejection,
+    // Set once the server's stdout closes, after which nothing can be answered
+    closed: Arc<AtomicBool>,
+    // Longest line read from the server's stdout; 0 means no limit
+    max_response_bytes: Arc<AtomicUsize>,
     server_info: Arc<Mutex<Option<InitializeResult>>>,
+    // Held for the whole initialize/initialized exchange so two handshakes
+    // never interleave
+    handshake: Mutex<()>,
+    // First non-JSON line the server wrote to stdout before any valid message
+    startup_noise: watch::Receiver<Option<String>>,
+    // Sent as `_meta` in the initialize params
+    init_meta: Option<Value>,
+    // Declared in the initialize params
+    capabilities: ClientCapabilities,
+    // Directories given with `--root`
+    roots: Vec<Root>,
     log_rx: Arc<Mutex<mpsc::UnboundedReceiver<String>>>,
 }
 
@@ -38,7 +150,7 @@ impl McpClient {
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
             .spawn()
-            .context("Failed to spawn MCP server process")?;
+            .map_err(|e| spawn_error(command, e))?;
 
         let stdin = child
             .stdin
@@ -56,63 +168,152 @@ impl McpClient {
         let (response_tx, response_rx) = mpsc::unbounded_channel();
         let (log_tx, log_rx) = mpsc::unbounded_channel();
         let pending_requests = Arc::new(Mutex::new(HashMap::new()));
+        let batch_rejection = Arc::new(Mutex::new(None));
+        let (noise_tx, noise_rx) = watch::channel(None);
+        let closed = Arc::new(AtomicBool::new(false));
+        let max_response_bytes = Arc::new(AtomicUsize::new(DEFAULT_MAX_RESPONSE_BYTES));
 
         let client = Self {
             child: Arc::new(Mutex::new(child)),
             stdin: Arc::new(Mutex::new(stdin)),
             request_id: AtomicI64::new(1),
+            id_scheme: IdScheme::default(),
             response_tx: response_tx.clone(),
             response_rx: Arc::new(Mutex::new(response_rx)),
             pending_requests: pending_requests.clone(),
+            batch_rejection: batch_rejection.clone(),
+            closed: closed.clone(),
+            max_response_bytes: max_response_bytes.clone(),
             server_info: Arc::new(Mutex::new(None)),
+            handshake: Mutex::new(()),
+            startup_noise: noise_rx,
+            init_meta: None,
+            capabilities: ClientCapabilities::default(),
+            roots: Vec::new(),
             log_rx: Arc::new(Mutex::new(log_rx)),
         };
 
-        tokio::spawn(Self::read_loop(stdout, response_tx, pending_requests));
+        tokio::spawn(Self::read_loop(
+            stdout,
+            response_tx,
+            pending_requests,
+            batch_rejection,
+            log_tx.clone(),
+            noise_tx,
+            closed,
+            max_response_bytes,
+        ));
         tokio::spawn(Self::log_loop(stderr, log_tx));
 
         Ok(client)
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn read_loop(
         stdout: ChildStdout,
         response_tx: mpsc::UnboundedSender<ResponseMessage>,
-        pending_requests: Arc<Mutex<HashMap<i64, oneshot::Sender<JsonRpcResponse>>>>,
+        pending_requests: PendingRequests,
+        batch_rejection: BatchRejection,
+        log_tx: mpsc::UnboundedSender<String>,
+        noise_tx: watch::Sender<Option<String>>,
+        closed: Arc<AtomicBool>,
+        max_response_bytes: Arc<AtomicUsize>,
     ) {
         let mut reader = BufReader::new(stdout);
         let mut line = String::new();
+        let mut seen_valid = false;
 
         loop {
             line.clear();
-            match reader.read_line(&mut line).await {
-                Ok(0) => {
+            let max = match max_response_bytes.load(Ordering::Relaxed) {
+                0 => usize::MAX,
+                max => max,
+            };
+            match read_bounded_line(&mut reader, &mut line, max).await {
+                Ok(LineRead::Eof) => {
                     debug!("Server stdout closed");
                     break;
                 }
-                Ok(_) => {
+                Ok(LineRead::Dropped { len, head, tail }) => {
+                    error!(
+                        "Dropped a {} byte message from the server, over the {} byte limit",
+                        len, max
+                    );
+                    let _ = log_tx.send(format!(
+                        "[stdout, dropped] {} byte message over the --max-response-bytes limit of {}\n",
+                        len, max
+                    ));
+                    // Fail the request it answered rather than leave it to
+                    // time out
+                    if let Some(id) = dropped_message_id(&head, &tail) {
+                        let error = serde_json::json!({
+                            "jsonrpc": "2.0",
+                            "id": id.to_value(),
+                            "error": {
+                                "code": -32603,
+                                "message": format!(
+                                    "Response of {} bytes exceeds the {} byte limit (--max-response-bytes)",
+                                    len, max
+                                ),
+                            },
+                        });
+                        Self::handle_message(
+                            &error.to_string(),
+                            &response_tx,
+                            &pending_requests,
+                            &batch_rejection,
+                        )
+                        .await;
+                    }
+                }
+                Ok(LineRead::Line) => {
                     let trimmed = line.trim();
                     if trimmed.is_empty() {
                         continue;
                     }
 
-                    debug!("Received: {}", trimmed);
-
-                    if let Ok(response) = serde_json::from_str::<JsonRpcResponse>(trimmed) {
-                        if let Value::Number(id) = &response.id {
-                            if let Some(id) = id.as_i64() {
-                                let mut pending = pending_requests.lock().await;
-                                if let Some(sender) = pending.remove(&id) {
-                                    let _ = sender.send(response);
-                                    continue;
+                    // A batch response is an array of individual messages
+                    let parsed = if trimmed.starts_with('[') {
+                        match serde_json::from_str::<Vec<Value>>(trimmed) {
+                            Ok(messages) => {
+                                for message in messages {
+                                    Self::handle_message(
+                                        &message.to_string(),
+                                        &response_tx,
+                                        &pending_requests,
+                                        &batch_rejection,
+                                    )
+                                    .await;
                                 }
+                                true
                             }
+                            Err(_) => false,
+                        }
+                    } else {
+                        Self::handle_message(
+                            trimmed,
+                            &response_tx,
+                            &pending_requests,
+                            &batch_rejection,
+                        )
+                        .await
+                    };
+
+                    if parsed {
+                        // Output before the first message no longer matters
+                        // once the server is talking JSON-RPC
+                        if !seen_valid {
+                            noise_tx.send_replace(None);
                         }
-                        let _ = response_tx.send(ResponseMessage::Response(response));
-                    } else if let Ok(notification) = serde_json::from_str::<JsonRpcRequest>(trimmed)
-                    {
-                        let _ = response_tx.send(ResponseMessage::Notification(notification));
+                        seen_valid = true;
                     } else {
                         warn!("Failed to parse message: {}", trimmed);
+                        // Show it alongside stderr, as it's usually a banner or
+                        // stack trace printed to the wrong stream
+                        let _ = log_tx.send(format!("[stdout, not JSON-RPC] {}\n", trimmed));
+                        if !seen_valid && noise_tx.borrow().is_none() {
+                            let _ = noise_tx.send(Some(trimmed.to_string()));
+                        }
                     }
                 }
                 Err(e) => {
@@ -121,6 +322,75 @@ impl McpClient {
                 }
             }
         }
+
+        Self::mark_closed(&closed, &pending_requests, &batch_rejection).await;
+    }
+
+    /// Record that the server can no longer answer, failing requests still
+    /// waiting rather than leaving them to time out. The flag is set first
+    /// so a request registered after the drain sees it.
+    async fn mark_closed(
+        closed: &AtomicBool,
+        pending_requests: &PendingRequests,
+        batch_rejection: &BatchRejection,
+    ) {
+        closed.store(true, Ordering::SeqCst);
+        pending_requests.lock().await.clear();
+        batch_rejection.lock().await.take();
+    }
+
+    /// Whether the connection is gone: the server closed its stdout, or
+    /// stopped reading its stdin.
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+    }
+
+    async fn handle_message(
+        message: &str,
+        response_tx: &mpsc::UnboundedSender<ResponseMessage>,
+        pending_requests: &PendingRequests,
+        batch_rejection: &BatchRejection,
+    ) -> bool {
+        if let Ok(response) = serde_json::from_str::<JsonRpcResponse>(message) {
+            match RequestId::from_value(&response.id) {
+                Some(id) => {
+                    debug!(request_id = %id, "Received: {}", message);
+                    let mut pending = pending_requests.lock().await;
+                    let sender = pending.remove(&id).or_else(|| match &id {
+                        // Some servers echo a numeric id back as a string
+                        RequestId::String(s) => s
+                            .parse::<i64>()
+                            .ok()
+                            .and_then(|n| pending.remove(&RequestId::Number(n))),
+                        RequestId::Number(_) => None,
+                    });
+                    if let Some(sender) = sender {
+                        let _ = sender.send(response);
+                        return true;
+                    }
+                }
+                // An error without an id while a batch is in flight means the
+                // server couldn't handle the batch as a whole
+                None if response.id.is_null() => {
+                    debug!("Received: {}", message);
+                    if let Some(error) = &response.error {
+                        if let Some(sender) = batch_rejection.lock().await.take() {
+                            let _ = sender.send(error.clone());
+                            return true;
+                        }
+                    }
+                }
+                None => debug!("Received: {}", message),
+            }
+            let _ = response_tx.send(ResponseMessage::Response(response));
+            true
+        } else if let Ok(notification) = serde_json::from_str::<JsonRpcRequest>(message) {
+            debug!(method = %notification.method, "Received: {}", message);
+            let _ = response_tx.send(ResponseMessage::Notification(notification));
+            true
+        } else {
+            false
+        }
     }
 
     async fn log_loop(stderr: ChildStderr, log_tx: mpsc::UnboundedSender<String>) {
@@ -149,13 +419,30 @@ impl McpClient {
 
     async fn send_request(&self, request: JsonRpcRequest) -> Result<()> {
         let json = serde_json::to_string(&request)?;
-        debug!("Sending: {}", json);
+        match &request.id {
+            Some(id) => debug!(request_id = %id, method = %request.method, "Sending: {}", json),
+            None => debug!(method = %request.method, "Sending: {}", json),
+        }
 
-        let mut stdin = self.stdin.lock().await;
-        stdin.write_all(json.as_bytes()).await?;
-        stdin.write_all(b"\n").await?;
-        stdin.flush().await?;
+        self.write_frame(&json).await
+    }
+
+    /// Write one line to the server. A failed write means the server is gone,
+    /// so the whole connection is marked closed, not just this request.
+    async fn write_frame(&self, json: &str) -> Result<()> {
+        let written = async {
+            let mut stdin = self.stdin.lock().await;
+            stdin.write_all(json.as_bytes()).await?;
+            stdin.write_all(b"\n").await?;
+            stdin.flush().await
+        }
+        .await;
 
+        if let Err(e) = written {
+            error!("Failed to write to server: {}", e);
+            Self::mark_closed(&self.closed, &self.pending_requests, &self.batch_rejection).await;
+            return Err(anyhow::Error::new(e).context(SERVER_STDIN_CLOSED));
+        }
         Ok(())
     }
 
@@ -164,26 +451,53 @@ impl McpClient {
         method: &str,
         params: Option<P>,
     ) -> Result<R> {
-        let id = self.request_id.fetch_add(1, Ordering::SeqCst);
+        let id = self.next_id();
         let params = params
             .map(|p| serde_json::to_value(p))
             .transpose()
             .context("Failed to serialize params")?;
 
-        let request = JsonRpcRequest::new(id, method, params);
+        let request = JsonRpcRequest::new(id.clone(), method, params);
 
         let (tx, rx) = oneshot::channel();
         {
             let mut pending = self.pending_requests.lock().await;
-            pending.insert(id, tx);
+            pending.insert(id.clone(), tx);
+        }
+        if self.closed.load(Ordering::SeqCst) {
+            self.pending_requests.lock().await.remove(&id);
+            anyhow::bail!(SERVER_CLOSED);
         }
 
-        self.send_request(request).await?;
+        if let Err(e) = self.send_request(request).await {
+            self.pending_requests.lock().await.remove(&id);
+            return Err(e);
+        }
 
         let response = tokio::time::timeout(std::time::Duration::from_secs(30), rx)
             .await
-            .context("Request timed out")??;
+            .context("Request timed out")?
+            .map_err(|_| anyhow::anyhow!(SERVER_CLOSED))?;
+
+        Self::parse_response(&id, method, response)
+    }
+
+    /// Allocate the id for the next request, following the id scheme.
+    fn next_id(&self) -> RequestId {
+        let n = self.request_id.fetch_add(1, Ordering::SeqCst);
+        match self.id_scheme {
+            IdScheme::Number => RequestId::Number(n),
+            IdScheme::String => RequestId::String(format!("mcpeek-{}", n)),
+            IdScheme::Uuid => RequestId::String(uuid::Uuid::new_v4().to_string()),
+        }
+    }
 
+    /// Turn a response into the typed result of the request it answers.
+    fn parse_response<R: serde::de::DeserializeOwned>(
+        id: &RequestId,
+        method: &str,
+        response: JsonRpcResponse,
+    ) -> Result<R> {
         if let Some(error) = response.error {
             anyhow::bail!("RPC error: {} (code: {})", error.message, error.code);
         }
@@ -191,6 +505,8 @@ impl McpClient {
         let result = response.result.context("Response missing result field")?;
 
         debug!(
+            request_id = %id,
+            method,
             "Deserializing result: {}",
             serde_json::to_string_pretty(&result).unwrap_or_default()
         );
@@ -202,20 +518,186 @@ impl McpClient {
         })
     }
 
-    pub async fn initialize(&self) -> Result<InitializeResult> {
-        let params = InitializeParams {
-            protocol_version: "2024-11-05".to_string(),
-            capabilities: ClientCapabilities {
-                roots: None,
-                sampling: None,
+    /// Send several requests as a single JSON-RPC batch (one array frame),
+    /// returning the responses in request order. Fails as a whole if the
+    /// server rejects the batch or doesn't answer all of it in time.
+    async fn call_batch(
+        &self,
+        calls: &[(&str, Option<Value>)],
+    ) -> Result<Vec<(RequestId, JsonRpcResponse)>> {
+        let mut requests = Vec::new();
+        let mut receivers = Vec::new();
+        {
+            let mut pending = self.pending_requests.lock().await;
+            for (method, params) in calls {
+                let id = self.next_id();
+                let (tx, rx) = oneshot::channel();
+                pending.insert(id.clone(), tx);
+                requests.push(JsonRpcRequest::new(id.clone(), *method, params.clone()));
+                receivers.push((id, rx));
+            }
+        }
+        let ids: Vec<RequestId> = receivers.iter().map(|(id, _)| id.clone()).collect();
+
+        let (reject_tx, reject_rx) = oneshot::channel();
+        *self.batch_rejection.lock().await = Some(reject_tx);
+
+        let responses = async {
+            let mut responses = Vec::new();
+            for (id, rx) in receivers {
+                responses.push((id, rx.await.context("Server closed the connection")?));
+            }
+            Ok::<_, anyhow::Error>(responses)
+        };
+        let result = async {
+            self.send_batch(&requests).await?;
+            tokio::select! {
+                responses = responses => responses,
+                Ok(error) = reject_rx => Err(anyhow::anyhow!(
+                    "Server rejected batch: {} (code: {})",
+                    error.message,
+                    error.code
+                )),
+            }
+        };
+        let result = tokio::time::timeout(BATCH_TIMEOUT, result)
+            .await
+            .unwrap_or_else(|_| Err(anyhow::anyhow!("Batch request timed out")));
+
+        *self.batch_rejection.lock().await = None;
+        if result.is_err() {
+            let mut pending = self.pending_requests.lock().await;
+            for id in ids {
+                pending.remove(&id);
+            }
+        }
+
+        result
+    }
+
+    async fn send_batch(&self, requests: &[JsonRpcRequest]) -> Result<()> {
+        let json = serde_json::to_string(requests)?;
+        for request in requests {
+            if let Some(id) = &request.id {
+                debug!(request_id = %id, method = %request.method, "Sending in batch");
+            }
+        }
+        debug!("Sending: {}", json);
+
+        self.write_frame(&json).await
+    }
+
+    /// List tools, prompts and resources for every capability the server
+    /// advertises. With `batch` set the requests go out as one JSON-RPC
+    /// batch, falling back to individual requests if the server can't
+    /// handle it.
+    pub async fn list_all(&self, batch: bool) -> ServerLists {
+        let capabilities = self
+            .get_server_info()
+            .await
+            .map(|info| info.capabilities)
+            .unwrap_or_default();
+
+        let mut methods = Vec::new();
+        if capabilities.tools.is_some() {
+            methods.push("tools/list");
+        }
+        if capabilities.prompts.is_some() {
+            methods.push("prompts/list");
+        }
+        if capabilities.resources.is_some() {
+            methods.push("resources/list");
+        }
+
+        if batch && methods.len() > 1 {
+            let calls: Vec<_> = methods.iter().map(|m| (*m, None)).collect();
+            match self.call_batch(&calls).await {
+                Ok(responses) => {
+                    let mut lists = ServerLists {
+                        tools: None,
+                        prompts: None,
+                        resources: None,
+                    };
+                    for ((id, response), method) in responses.into_iter().zip(methods) {
+                        match method {
+                            "tools/list" => {
+                                lists.tools = Some(
+                                    Self::parse_response::<ListToolsResult>(&id, method, response)
+                                        .map(|r| r.tools),
+                                )
+                            }
+                            "prompts/list" => {
+                                lists.prompts = Some(
+                                    Self::parse_response::<ListPromptsResult>(
+                                        &id, method, response,
+                                    )
+                                    .map(|r| r.prompts),
+                                )
+                            }
+                            _ => {
+                                lists.resources = Some(
+                                    Self::parse_response::<ListResourcesResult>(
+                                        &id, method, response,
+                                    )
+                                    .map(|r| r.resources),
+                                )
+                            }
+                        }
+                    }
+                    return lists;
+                }
+                Err(e) => warn!("{:#}; falling back to individual requests", e),
+            }
+        }
+
+        ServerLists {
+            tools: match capabilities.tools {
+                Some(_) => Some(self.list_tools().await),
+                None => None,
             },
-            client_info: Implementation {
-                name: env!("CARGO_PKG_NAME").to_string(),
-                version: env!("CARGO_PKG_VERSION").to_string(),
+            prompts: match capabilities.prompts {
+                Some(_) => Some(self.list_prompts().await),
+                None => None,
             },
-        };
+            resources: match capabilities.resources {
+                Some(_) => Some(self.list_resources().await),
+                None => None,
+            },
+        }
+    }
 
-        let result: InitializeResult = self.call_method("initialize", Some(params)).await?;
+    /// Perform the `initialize` handshake, followed by the
+    /// `notifications/initialized` notification.
+    ///
+    /// Safe to call again on a running connection to re-handshake with
+    /// servers that support it: each call sends exactly one `initialize` and,
+    /// if it succeeds, exactly one `initialized`, and replaces the stored
+    /// server info. Concurrent calls are serialized. If the server rejects
+    /// the request the previous server info is kept and no notification is
+    /// sent.
+    pub async fn initialize(&self) -> Result<InitializeResult> {
+        let _handshake = self.handshake.lock().await;
+
+        let params = self.initialize_params();
+        let call = self.call_method::<_, InitializeResult>("initialize", Some(params));
+        tokio::pin!(call);
+
+        // Rather than waiting out the request timeout, give up soon after the
+        // server writes something that isn't JSON-RPC, unless it answers anyway
+        let mut noise = self.startup_noise.clone();
+        let noise_line = async move {
+            let line = noise.wait_for(Option::is_some).await.ok()?;
+            line.clone()
+        };
+        let result = tokio::select! {
+            result = &mut call => result?,
+            Some(line) = noise_line => {
+                match tokio::time::timeout(STARTUP_NOISE_GRACE, &mut call).await {
+                    Ok(result) => result?,
+                    Err(_) => anyhow::bail!("Server emitted non-JSON on stdout: {}", line),
+                }
+            }
+        };
 
         *self.server_info.lock().await = Some(result.clone());
 
@@ -225,6 +707,60 @@ impl McpClient {
         Ok(result)
     }
 
+    /// Generate request ids following `scheme` instead of plain integers.
+    pub fn with_id_scheme(mut self, scheme: IdScheme) -> Self {
+        self.id_scheme = scheme;
+        self
+    }
+
+    /// Drop messages longer than `max` bytes instead of buffering them,
+    /// failing the request they answer. 0 removes the limit.
+    pub fn with_max_response_bytes(self, max: usize) -> Self {
+        self.max_response_bytes.store(max, Ordering::Relaxed);
+        self
+    }
+
+    /// Send `meta` as the `_meta` field of the initialize params, for servers
+    /// that read custom handshake fields from it.
+    pub fn with_init_meta(mut self, meta: Option<Value>) -> Self {
+        self.init_meta = meta;
+        self
+    }
+
+    /// Declare `capabilities` in the initialize params instead of none, to
+    /// see how a server treats a different client. mcpeek still doesn't
+    /// answer the requests they invite, such as `sampling/createMessage`.
+    pub fn with_capabilities(mut self, capabilities: ClientCapabilities) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
+    /// Record the directories the server may access. They are shown to the
+    /// user but not yet offered to the server, as `roots/list` isn't
+    /// answered.
+    pub fn with_roots(mut self, roots: Vec<Root>) -> Self {
+        self.roots = roots;
+        self
+    }
+
+    pub fn roots(&self) -> &[Root] {
+        &self.roots
+    }
+
+    /// The parameters mcpeek sends with `initialize`, including the client
+    /// capabilities it declares.
+    pub fn initialize_params(&self) -> InitializeParams {
+        InitializeParams {
+            protocol_version: "2024-11-05".to_string(),
+            capabilities: self.capabilities.clone(),
+            client_info: Implementation {
+                name: env!("CARGO_PKG_NAME").to_string(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+            },
+            meta: self.init_meta.clone(),
+        }
+    }
+
     pub async fn list_tools(&self) -> Result<Vec<Tool>> {
         let result: ListToolsResult = self.call_method("tools/list", None::<()>).await?;
         Ok(result.tools)
@@ -267,8 +803,29 @@ impl McpClient {
     }
 
     pub async fn read_resource(&self, uri: &str) -> Result<Vec<ResourceContents>> {
+        self.read_resource_with_params(uri, Value::Null).await
+    }
+
+    /// Read a resource, adding the fields of `extra` (a JSON object, or null
+    /// for none) to the request params. This passes through params the
+    /// protocol doesn't define, such as a range for servers that support
+    /// partial reads.
+    pub async fn read_resource_with_params(
+        &self,
+        uri: &str,
+        extra: Value,
+    ) -> Result<Vec<ResourceContents>> {
+        let extra = match extra {
+            Value::Null => serde_json::Map::new(),
+            Value::Object(extra) => extra,
+            _ => anyhow::bail!("Extra resources/read params must be a JSON object"),
+        };
+        if extra.contains_key("uri") {
+            anyhow::bail!("Extra resources/read params can't override the uri");
+        }
         let params = ReadResourceParams {
             uri: uri.to_string(),
+            extra,
         };
 
         let result: ReadResourceResult = self
@@ -299,6 +856,132 @@ impl McpClient {
     }
 }
 
+/// Turn a failure to start the server into a message saying what to fix.
+fn spawn_error(command: &str, error: std::io::Error) -> anyhow::Error {
+    match error.kind() {
+        std::io::ErrorKind::NotFound if command.contains('/') => {
+            anyhow::anyhow!("Command '{}' not found", command)
+        }
+        std::io::ErrorKind::NotFound => {
+            anyhow::anyhow!("Command '{}' not found in PATH", command)
+        }
+        std::io::ErrorKind::PermissionDenied => anyhow::anyhow!(
+            "Permission denied running '{}' (is it executable?)",
+            command
+        ),
+        _ => anyhow::Error::new(error).context("Failed to spawn MCP server process"),
+    }
+}
+
+/// What `read_bounded_line` found on the server's stdout.
+enum LineRead {
+    Eof,
+    Line,
+    // Too long to keep: only its length and a little of each end
+    Dropped {
+        len: usize,
+        head: String,
+        tail: String,
+    },
+}
+
+/// Like `read_line`, but stops storing a line once it passes `max` bytes and
+/// skips the rest of it, so a runaway message can't exhaust memory.
+async fn read_bounded_line<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+    line: &mut String,
+    max: usize,
+) -> std::io::Result<LineRead> {
+    let mut bytes = Vec::new();
+    let mut tail = Vec::new();
+    let mut len = 0;
+    let mut dropped = false;
+
+    loop {
+        let available = reader.fill_buf().await?;
+        if available.is_empty() {
+            if len == 0 {
+                return Ok(LineRead::Eof);
+            }
+            break;
+        }
+        let (chunk, done) = match available.iter().position(|&b| b == b'\n') {
+            Some(end) => (&available[..=end], true),
+            None => (available, false),
+        };
+        let consumed = chunk.len();
+        len += consumed;
+        if dropped {
+            tail.extend_from_slice(chunk);
+            if tail.len() > 2 * DROPPED_PEEK_BYTES {
+                tail.drain(..tail.len() - DROPPED_PEEK_BYTES);
+            }
+        } else {
+            bytes.extend_from_slice(chunk);
+            if bytes.len() > max {
+                dropped = true;
+                tail = bytes.split_off(bytes.len() - DROPPED_PEEK_BYTES.min(bytes.len()));
+                bytes.truncate(DROPPED_PEEK_BYTES);
+            }
+        }
+        reader.consume(consumed);
+        if done {
+            break;
+        }
+    }
+
+    if dropped {
+        let tail = &tail[tail.len().saturating_sub(DROPPED_PEEK_BYTES)..];
+        return Ok(LineRead::Dropped {
+            len,
+            head: String::from_utf8_lossy(&bytes).into_owned(),
+            tail: String::from_utf8_lossy(tail).into_owned(),
+        });
+    }
+    *line = String::from_utf8(bytes)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    Ok(LineRead::Line)
+}
+
+/// Find the id of a message from the ends of it. The id counts when it comes
+/// before the result in `head`, or closes the message in `tail`, so an `"id"`
+/// nested inside the result isn't mistaken for it.
+fn dropped_message_id(head: &str, tail: &str) -> Option<RequestId> {
+    let before_result = head
+        .find("\"result\"")
+        .into_iter()
+        .chain(head.find("\"error\""))
+        .min()
+        .unwrap_or(head.len());
+    let from_head = head.find("\"id\"").filter(|&at| at < before_result);
+    if let Some((id, _)) = from_head.and_then(|at| id_after_key(&head[at..])) {
+        return Some(id);
+    }
+    let (id, rest) = id_after_key(&tail[tail.rfind("\"id\"")?..])?;
+    rest.trim_start()
+        .strip_prefix('}')
+        .filter(|rest| rest.trim().is_empty())
+        .map(|_| id)
+}
+
+/// Parse the value of the `"id"` key that `text` starts with, returning it
+/// and the text after it.
+fn id_after_key(text: &str) -> Option<(RequestId, &str)> {
+    let value = text["\"id\"".len()..]
+        .trim_start()
+        .strip_prefix(':')?
+        .trim_start();
+    let end = if let Some(string) = value.strip_prefix('"') {
+        string.find('"')? + 2
+    } else {
+        value
+            .find(|c: char| !(c.is_ascii_digit() || c == '-'))
+            .unwrap_or(value.len())
+    };
+    let id = RequestId::from_value(&serde_json::from_str(&value[..end]).ok()?)?;
+    Some((id, &value[end..]))
+}
+
 impl Drop for McpClient {
     fn drop(&mut self) {
         let child = self.child.clone();
@@ -313,6 +996,44 @@ mod tests {
     use super::*;
     use serde_json::json;
 
+    #[test]
+    fn test_server_lists_fill_from_retry() {
+        let tool: Tool = serde_json::from_value(json!({
+            "name": "search",
+            "inputSchema": {"type": "object"}
+        }))
+        .unwrap();
+        let mut lists = ServerLists {
+            tools: Some(Ok(vec![])),
+            prompts: Some(Err(anyhow::anyhow!("not ready"))),
+            resources: None,
+        };
+        assert!(lists.has_missing());
+
+        lists.fill_from(ServerLists {
+            tools: Some(Ok(vec![tool])),
+            prompts: Some(Ok(vec![])),
+            resources: Some(Ok(vec![])),
+        });
+        assert_eq!(lists.tools.as_ref().unwrap().as_ref().unwrap().len(), 1);
+        assert!(lists.prompts.as_ref().unwrap().is_ok());
+        // Not advertised the first time, so not taken from the retry
+        assert!(lists.resources.is_none());
+
+        // An empty list is kept over a failed retry
+        let mut lists = ServerLists {
+            tools: Some(Ok(vec![])),
+            prompts: None,
+            resources: None,
+        };
+        lists.fill_from(ServerLists {
+            tools: Some(Err(anyhow::anyhow!("gone"))),
+            prompts: None,
+            resources: None,
+        });
+        assert!(lists.tools.unwrap().is_ok());
+    }
+
     #[test]
     fn test_request_id_increments() {
         let request_id = AtomicI64::new(1);
@@ -337,6 +1058,34 @@ mod tests {
         assert!(json_str.contains("\"params\""));
     }
 
+    #[test]
+    fn test_batch_serialization() {
+        let requests = vec![
+            JsonRpcRequest::new(1, "tools/list", None),
+            JsonRpcRequest::new(2, "resources/list", None),
+        ];
+        let value = serde_json::to_value(&requests).unwrap();
+
+        let batch = value.as_array().unwrap();
+        assert_eq!(batch.len(), 2);
+        assert_eq!(batch[0]["id"], 1);
+        assert_eq!(batch[1]["method"], "resources/list");
+    }
+
+    #[test]
+    fn test_batch_response_parsing() {
+        let line = r#"[{"jsonrpc":"2.0","id":2,"result":{}},{"jsonrpc":"2.0","id":1,"error":{"code":-32601,"message":"Method not found"}}]"#;
+        let messages: Vec<Value> = serde_json::from_str(line).unwrap();
+
+        let responses: Vec<JsonRpcResponse> = messages
+            .into_iter()
+            .map(|m| serde_json::from_value(m).unwrap())
+            .collect();
+        assert_eq!(responses[0].id, json!(2));
+        assert!(responses[0].result.is_some());
+        assert_eq!(responses[1].error.as_ref().unwrap().code, -32601);
+    }
+
     #[test]
     fn test_notification_serialization() {
         let notification = JsonRpcRequest::notification("notifications/initialized", None);
@@ -387,11 +1136,13 @@ mod tests {
             capabilities: ClientCapabilities {
                 roots: None,
                 sampling: None,
+                extra: Default::default(),
             },
             client_info: Implementation {
                 name: env!("CARGO_PKG_NAME").to_string(),
                 version: env!("CARGO_PKG_VERSION").to_string(),
             },
+            meta: None,
         };
 
         assert_eq!(params.protocol_version, "2024-11-05");
@@ -431,6 +1182,7 @@ mod tests {
     fn test_read_resource_params_construction() {
         let params = ReadResourceParams {
             uri: "file:///path/to/resource".to_string(),
+            extra: serde_json::Map::new(),
         };
 
         assert_eq!(params.uri, "file:///path/to/resource");
@@ -451,6 +1203,53 @@ mod tests {
         assert!(map.contains_key(&1i64));
     }
 
+    #[tokio::test]
+    async fn test_response_routed_by_string_id() {
+        let pending: PendingRequests = Arc::new(Mutex::new(HashMap::new()));
+        let batch_rejection: BatchRejection = Arc::new(Mutex::new(None));
+        let (response_tx, mut response_rx) = mpsc::unbounded_channel();
+        let (tx, rx) = oneshot::channel();
+        pending
+            .lock()
+            .await
+            .insert(RequestId::String("mcpeek-3".to_string()), tx);
+
+        let handled = McpClient::handle_message(
+            r#"{"jsonrpc":"2.0","id":"mcpeek-3","result":{}}"#,
+            &response_tx,
+            &pending,
+            &batch_rejection,
+        )
+        .await;
+
+        assert!(handled);
+        assert_eq!(rx.await.unwrap().id, json!("mcpeek-3"));
+        assert!(pending.lock().await.is_empty());
+        assert!(response_rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_response_with_stringified_numeric_id() {
+        let pending: PendingRequests = Arc::new(Mutex::new(HashMap::new()));
+        let batch_rejection: BatchRejection = Arc::new(Mutex::new(None));
+        let (response_tx, mut response_rx) = mpsc::unbounded_channel();
+        let (tx, rx) = oneshot::channel();
+        pending.lock().await.insert(RequestId::Number(5), tx);
+
+        let handled = McpClient::handle_message(
+            r#"{"jsonrpc":"2.0","id":"5","result":{"ok":true}}"#,
+            &response_tx,
+            &pending,
+            &batch_rejection,
+        )
+        .await;
+
+        assert!(handled);
+        assert_eq!(rx.await.unwrap().result, Some(json!({"ok": true})));
+        assert!(pending.lock().await.is_empty());
+        assert!(response_rx.try_recv().is_err());
+    }
+
     #[tokio::test]
     async fn test_server_info_storage() {
         let server_info = Arc::new(Mutex::new(None));
@@ -472,6 +1271,146 @@ mod tests {
         assert_eq!(stored.as_ref().unwrap().server_info.name, "test_server");
     }
 
+    #[tokio::test]
+    async fn test_read_resource_with_params_rejects_bad_extra() {
+        let client = McpClient::new("cat", &[]).await.unwrap();
+
+        let error = client
+            .read_resource_with_params("file:///a.txt", json!(["range"]))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Extra resources/read params must be a JSON object"
+        );
+
+        let error = client
+            .read_resource_with_params("file:///a.txt", json!({"uri": "file:///b.txt"}))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Extra resources/read params can't override the uri"
+        );
+
+        client.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_spawn_missing_command() {
+        let error = McpClient::new("mcpeek-no-such-command", &[])
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Command 'mcpeek-no-such-command' not found in PATH"
+        );
+
+        let error = McpClient::new("/nonexistent/server", &[])
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(error.to_string(), "Command '/nonexistent/server' not found");
+    }
+
+    #[tokio::test]
+    async fn test_initialize_fails_on_non_json_stdout() {
+        let args = vec![
+            "-c".to_string(),
+            "echo 'Starting up...'; sleep 10".to_string(),
+        ];
+        let client = McpClient::new("sh", &args).await.unwrap();
+
+        let error = client.initialize().await.err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Server emitted non-JSON on stdout: Starting up..."
+        );
+        assert!(client
+            .get_logs()
+            .await
+            .contains(&"[stdout, not JSON-RPC] Starting up...\n".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_request_fails_when_server_exits() {
+        let args = vec!["-c".to_string(), "exit 1".to_string()];
+        let client = McpClient::new("sh", &args).await.unwrap();
+
+        // Depending on timing the request fails to be written or goes
+        // unanswered; either way it must not wait for the request timeout
+        let started = std::time::Instant::now();
+        assert!(client.initialize().await.is_err());
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+        assert!(client.pending_requests.lock().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_write_failure_closes_connection() {
+        // The server keeps stdout open but stops reading its stdin
+        let args = vec!["-c".to_string(), "exec 0<&-; sleep 10".to_string()];
+        let client = McpClient::new("sh", &args).await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        assert!(!client.is_closed());
+
+        let error = client.list_tools().await.unwrap_err();
+        assert_eq!(error.to_string(), SERVER_STDIN_CLOSED);
+        assert!(client.is_closed());
+        assert!(client.pending_requests.lock().await.is_empty());
+
+        // Later requests fail straight away
+        let error = client.list_prompts().await.unwrap_err();
+        assert!(error.to_string().contains("has it exited?"));
+    }
+
+    #[tokio::test]
+    async fn test_read_bounded_line_drops_long_lines() {
+        let mut input: &[u8] = b"{\"id\":1}\n0123456789abcdef\n{\"id\":2}";
+        let mut line = String::new();
+
+        assert!(matches!(
+            read_bounded_line(&mut input, &mut line, 10).await.unwrap(),
+            LineRead::Line
+        ));
+        assert_eq!(line, "{\"id\":1}\n");
+        match read_bounded_line(&mut input, &mut line, 10).await.unwrap() {
+            LineRead::Dropped { len, .. } => assert_eq!(len, 17),
+            _ => panic!("expected the long line to be dropped"),
+        }
+        // Reading carries on with the next line
+        assert!(matches!(
+            read_bounded_line(&mut input, &mut line, 10).await.unwrap(),
+            LineRead::Line
+        ));
+        assert_eq!(line, "{\"id\":2}");
+        assert!(matches!(
+            read_bounded_line(&mut input, &mut line, 10).await.unwrap(),
+            LineRead::Eof
+        ));
+    }
+
+    #[test]
+    fn test_dropped_message_id() {
+        assert_eq!(
+            dropped_message_id(r#"{"jsonrpc":"2.0","id":7,"result":{"conte"#, ""),
+            Some(RequestId::Number(7))
+        );
+        assert_eq!(
+            dropped_message_id(r#"{"jsonrpc":"2.0", "id" : "req-1", "result""#, ""),
+            Some(RequestId::String("req-1".to_string()))
+        );
+        // An id inside the result isn't the message's
+        assert_eq!(
+            dropped_message_id(r#"{"result":{"items":[{"id":3"#, r#"xyz"}]}}"#),
+            None
+        );
+        assert_eq!(
+            dropped_message_id(r#"{"result":{"items":[{"id":3"#, r#"xyz"}]},"id":12}"#),
+            Some(RequestId::Number(12))
+        );
+    }
+
     #[test]
     fn test_jsonrpc_error_structure() {
         let error = JsonRpcError {
@@ -0,0 +1,99 @@
+use anyhow::{bail, Result};
+
+/// Expand `${VAR}` references in `input` from the process environment.
+///
+/// A `$` not followed by `{` is kept literally, and `$${` is written as a
+/// literal `${` without expanding what follows. Referencing a variable that
+/// is not set, or leaving a `${` unclosed, is an error rather than silently
+/// producing an empty string.
+pub fn expand_env_vars(input: &str) -> Result<String> {
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+
+    while let Some(start) = rest.find("${") {
+        let after = &rest[start + 2..];
+        if let Some(before) = rest[..start].strip_suffix('$') {
+            output.push_str(before);
+            output.push_str("${");
+            rest = after;
+            continue;
+        }
+        output.push_str(&rest[..start]);
+
+        let Some(end) = after.find('}') else {
+            bail!("Unclosed '${{' in '{}'", input);
+        };
+
+        let name = &after[..end];
+        if name.is_empty() {
+            bail!("Empty variable reference '${{}}' in '{}'", input);
+        }
+
+        match std::env::var(name) {
+            Ok(value) => output.push_str(&value),
+            Err(_) => bail!("Environment variable '{}' is not set", name),
+        }
+
+        rest = &after[end + 1..];
+    }
+
+    output.push_str(rest);
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_known_variable() {
+        std::env::set_var("MCPEEK_TEST_EXPAND_DIR", "/opt/servers");
+
+        let expanded = expand_env_vars("${MCPEEK_TEST_EXPAND_DIR}/bin/server").unwrap();
+        assert_eq!(expanded, "/opt/servers/bin/server");
+    }
+
+    #[test]
+    fn test_expand_multiple_variables() {
+        std::env::set_var("MCPEEK_TEST_EXPAND_A", "a");
+        std::env::set_var("MCPEEK_TEST_EXPAND_B", "b");
+
+        let expanded =
+            expand_env_vars("--x=${MCPEEK_TEST_EXPAND_A}-${MCPEEK_TEST_EXPAND_B}").unwrap();
+        assert_eq!(expanded, "--x=a-b");
+    }
+
+    #[test]
+    fn test_plain_dollar_is_literal() {
+        assert_eq!(expand_env_vars("cost: $5").unwrap(), "cost: $5");
+        assert_eq!(expand_env_vars("no vars here").unwrap(), "no vars here");
+    }
+
+    #[test]
+    fn test_escaped_reference_is_literal() {
+        std::env::set_var("MCPEEK_TEST_EXPAND_ESCAPE", "x");
+
+        assert_eq!(
+            expand_env_vars("--template=$${NAME}").unwrap(),
+            "--template=${NAME}"
+        );
+        assert_eq!(
+            expand_env_vars("$${MCPEEK_TEST_EXPAND_ESCAPE}-${MCPEEK_TEST_EXPAND_ESCAPE}").unwrap(),
+            "${MCPEEK_TEST_EXPAND_ESCAPE}-x"
+        );
+    }
+
+    #[test]
+    fn test_missing_variable_is_error() {
+        let err = expand_env_vars("${MCPEEK_TEST_EXPAND_UNSET_VARIABLE}").unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("MCPEEK_TEST_EXPAND_UNSET_VARIABLE"));
+    }
+
+    #[test]
+    fn test_unclosed_reference_is_error() {
+        assert!(expand_env_vars("${HOME").is_err());
+        assert!(expand_env_vars("${}").is_err());
+    }
+}
@@ -1,4 +1,6 @@
 pub mod client;
+pub mod env;
 pub mod protocol;
+pub mod schema;
 
-pub use client::McpClient;
+pub use client::{IdScheme, McpClient, ServerLists, DEFAULT_MAX_RESPONSE_BYTES};
@@ -1,6 +1,61 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
+use std::fmt;
+
+/// Id of a JSON-RPC request, used to match it with its response.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum RequestId {
+    Number(i64),
+    String(String),
+}
+
+impl RequestId {
+    /// The id carried by a message, if it is one we could have sent.
+    pub fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Number(n) => n.as_i64().map(RequestId::Number),
+            Value::String(s) => Some(RequestId::String(s.clone())),
+            _ => None,
+        }
+    }
+
+    pub fn to_value(&self) -> Value {
+        match self {
+            RequestId::Number(n) => Value::Number((*n).into()),
+            RequestId::String(s) => Value::String(s.clone()),
+        }
+    }
+}
+
+impl From<i64> for RequestId {
+    fn from(id: i64) -> Self {
+        RequestId::Number(id)
+    }
+}
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RequestId::Number(n) => write!(f, "{}", n),
+            RequestId::String(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+/// Names that occur more than once, in order of first repeat. Servers that
+/// define two tools (or prompts, or resources) with one name make calls
+/// ambiguous.
+pub fn duplicate_names<'a>(names: impl IntoIterator<Item = &'a str>) -> Vec<&'a str> {
+    let mut seen = std::collections::HashSet::new();
+    let mut duplicates = Vec::new();
+    for name in names {
+        if !seen.insert(name) && !duplicates.contains(&name) {
+            duplicates.push(name);
+        }
+    }
+    duplicates
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JsonRpcRequest {
@@ -13,10 +68,10 @@ pub struct JsonRpcRequest {
 }
 
 impl JsonRpcRequest {
-    pub fn new(id: i64, method: impl Into<String>, params: Option<Value>) -> Self {
+    pub fn new(id: impl Into<RequestId>, method: impl Into<String>, params: Option<Value>) -> Self {
         Self {
             jsonrpc: "2.0".to_string(),
-            id: Some(Value::Number(id.into())),
+            id: Some(id.into().to_value()),
             method: method.into(),
             params,
         }
@@ -59,22 +114,37 @@ pub struct InitializeParams {
     pub capabilities: ClientCapabilities,
     #[serde(rename = "clientInfo")]
     pub client_info: Implementation,
+    #[serde(rename = "_meta")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Value>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ClientCapabilities {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub roots: Option<RootsCapability>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sampling: Option<HashMap<String, Value>>,
+    /// Other capabilities to declare, e.g. `elicitation` or `experimental`
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RootsCapability {
-    #[serde(rename = "listChanged")]
+    #[serde(rename = "listChanged", default)]
     pub list_changed: bool,
 }
 
+/// A directory the client makes available to the server, as listed by
+/// `roots/list`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Root {
+    pub uri: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Implementation {
     pub name: String,
@@ -102,6 +172,21 @@ pub struct ServerCapabilities {
     pub resources: Option<ResourcesCapability>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub logging: Option<HashMap<String, Value>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub experimental: Option<HashMap<String, Value>>,
+}
+
+impl ServerCapabilities {
+    /// Names of the experimental capabilities the server declared, sorted.
+    pub fn experimental_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self
+            .experimental
+            .iter()
+            .flat_map(|e| e.keys().map(String::as_str))
+            .collect();
+        names.sort_unstable();
+        names
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -137,9 +222,27 @@ pub struct ListToolsResult {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Tool {
     pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
     #[serde(rename = "inputSchema")]
     pub input_schema: Value,
+    #[serde(rename = "outputSchema", skip_serializing_if = "Option::is_none")]
+    pub output_schema: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<Value>,
+    /// Fields mcpeek doesn't model, kept to show the definition as sent
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, Value>,
+}
+
+impl Tool {
+    /// Look up a boolean behaviour hint (e.g. `destructiveHint`) in the
+    /// tool's annotations.
+    pub fn hint(&self, name: &str) -> Option<bool> {
+        self.annotations.as_ref()?.get(name)?.as_bool()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -152,6 +255,9 @@ pub struct CallToolParams {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CallToolResult {
     pub content: Vec<ToolContent>,
+    #[serde(rename = "structuredContent")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub structured_content: Option<Value>,
     #[serde(rename = "isError")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub is_error: Option<bool>,
@@ -182,9 +288,13 @@ pub struct ListPromptsResult {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Prompt {
     pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub arguments: Option<Vec<PromptArgument>>,
+    /// Fields mcpeek doesn't model, kept to show the definition as sent
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -246,14 +356,21 @@ pub struct ListResourcesResult {
 pub struct Resource {
     pub uri: String,
     pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
-    #[serde(rename = "mimeType")]
+    #[serde(rename = "mimeType", skip_serializing_if = "Option::is_none")]
     pub mime_type: Option<String>,
+    /// Fields mcpeek doesn't model, kept to show the definition as sent
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReadResourceParams {
     pub uri: String,
+    /// Extra params passed through as-is, e.g. a range a server supports
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -293,6 +410,30 @@ mod tests {
         assert!(request.params.is_some());
     }
 
+    #[test]
+    fn test_request_id_values() {
+        let request = JsonRpcRequest::new(RequestId::String("abc".to_string()), "ping", None);
+        assert_eq!(request.id, Some(json!("abc")));
+
+        assert_eq!(RequestId::from_value(&json!(7)), Some(RequestId::Number(7)));
+        assert_eq!(
+            RequestId::from_value(&json!("abc")),
+            Some(RequestId::String("abc".to_string()))
+        );
+        assert_eq!(RequestId::from_value(&json!(null)), None);
+        assert_eq!(RequestId::from_value(&json!(1.5)), None);
+        assert_eq!(RequestId::String("abc".to_string()).to_string(), "abc");
+    }
+
+    #[test]
+    fn test_duplicate_names() {
+        assert!(duplicate_names(["a", "b", "c"]).is_empty());
+        assert_eq!(
+            duplicate_names(["a", "b", "a", "c", "b", "a"]),
+            vec!["a", "b"]
+        );
+    }
+
     #[test]
     fn test_jsonrpc_request_notification() {
         let notification = JsonRpcRequest::notification("test_notification", None);
@@ -358,11 +499,13 @@ mod tests {
             capabilities: ClientCapabilities {
                 roots: Some(RootsCapability { list_changed: true }),
                 sampling: None,
+                extra: Default::default(),
             },
             client_info: Implementation {
                 name: "test_client".to_string(),
                 version: "1.0.0".to_string(),
             },
+            meta: None,
         };
 
         let json_str = serde_json::to_string(&params).unwrap();
@@ -374,6 +517,27 @@ mod tests {
         assert_eq!(parsed.client_info.name, "test_client");
     }
 
+    #[test]
+    fn test_initialize_params_meta() {
+        let params = InitializeParams {
+            protocol_version: "2024-11-05".to_string(),
+            capabilities: ClientCapabilities {
+                roots: None,
+                sampling: None,
+                extra: Default::default(),
+            },
+            client_info: Implementation {
+                name: "test".to_string(),
+                version: "1.0".to_string(),
+            },
+            meta: Some(serde_json::json!({"tenant": "acme"})),
+        };
+
+        let value = serde_json::to_value(&params).unwrap();
+        assert_eq!(value["_meta"]["tenant"], "acme");
+        assert_eq!(value["clientInfo"]["name"], "test");
+    }
+
     #[test]
     fn test_initialize_params_optional_fields_skipped() {
         let params = InitializeParams {
@@ -381,16 +545,19 @@ mod tests {
             capabilities: ClientCapabilities {
                 roots: None,
                 sampling: None,
+                extra: Default::default(),
             },
             client_info: Implementation {
                 name: "test".to_string(),
                 version: "1.0".to_string(),
             },
+            meta: None,
         };
 
         let json_str = serde_json::to_string(&params).unwrap();
         assert!(!json_str.contains("roots"));
         assert!(!json_str.contains("sampling"));
+        assert!(!json_str.contains("_meta"));
     }
 
     #[test]
@@ -399,12 +566,56 @@ mod tests {
         assert!(caps.tools.is_none());
         assert!(caps.prompts.is_none());
         assert!(caps.resources.is_none());
+        assert!(caps.experimental.is_none());
+    }
+
+    #[test]
+    fn test_server_capabilities_experimental() {
+        let json =
+            r#"{"tools": {}, "experimental": {"streaming": {"chunked": true}, "batch": {}}}"#;
+        let caps: ServerCapabilities = serde_json::from_str(json).unwrap();
+
+        assert!(caps.tools.is_some());
+        assert_eq!(caps.experimental_names(), vec!["batch", "streaming"]);
+        assert_eq!(
+            caps.experimental.as_ref().unwrap()["streaming"]["chunked"],
+            true
+        );
+
+        let caps: ServerCapabilities = serde_json::from_str("{}").unwrap();
+        assert!(caps.experimental_names().is_empty());
+    }
+
+    #[test]
+    fn test_client_capabilities_from_json() {
+        let raw = json!({"roots": {}, "elicitation": {}});
+        let capabilities: ClientCapabilities = serde_json::from_value(raw).unwrap();
+        assert!(!capabilities.roots.as_ref().unwrap().list_changed);
+        assert!(capabilities.sampling.is_none());
+        assert_eq!(
+            serde_json::to_value(&capabilities).unwrap(),
+            json!({"roots": {"listChanged": false}, "elicitation": {}})
+        );
+    }
+
+    #[test]
+    fn test_unknown_fields_round_trip() {
+        let raw = json!({
+            "name": "search",
+            "inputSchema": {"type": "object"},
+            "icons": [{"src": "https://example.com/search.png"}],
+            "_meta": {"vendor": "acme"}
+        });
+        let tool: Tool = serde_json::from_value(raw.clone()).unwrap();
+        assert_eq!(tool.extra.len(), 2);
+        assert_eq!(serde_json::to_value(&tool).unwrap(), raw);
     }
 
     #[test]
     fn test_tool_serialization() {
         let tool = Tool {
             name: "test_tool".to_string(),
+            title: None,
             description: Some("A test tool".to_string()),
             input_schema: json!({
                 "type": "object",
@@ -412,6 +623,9 @@ mod tests {
                     "arg1": {"type": "string"}
                 }
             }),
+            output_schema: None,
+            annotations: None,
+            extra: Default::default(),
         };
 
         let json_str = serde_json::to_string(&tool).unwrap();
@@ -483,6 +697,7 @@ mod tests {
             content: vec![ToolContent::Text {
                 text: "Success".to_string(),
             }],
+            structured_content: None,
             is_error: Some(false),
         };
 
@@ -494,6 +709,21 @@ mod tests {
         assert_eq!(parsed.is_error, Some(false));
     }
 
+    #[test]
+    fn test_call_tool_result_structured_content() {
+        let json_str = r#"{
+            "content": [{"type": "text", "text": "{\"temp\": 21}"}],
+            "structuredContent": {"temp": 21}
+        }"#;
+
+        let parsed: CallToolResult = serde_json::from_str(json_str).unwrap();
+        assert_eq!(parsed.structured_content, Some(json!({"temp": 21})));
+
+        let value = serde_json::to_value(&parsed).unwrap();
+        assert_eq!(value["structuredContent"]["temp"], 21);
+        assert!(value.get("isError").is_none());
+    }
+
     #[test]
     fn test_prompt_with_arguments() {
         let prompt = Prompt {
@@ -504,6 +734,7 @@ mod tests {
                 description: Some("First arg".to_string()),
                 required: Some(true),
             }]),
+            extra: Default::default(),
         };
 
         let json_str = serde_json::to_string(&prompt).unwrap();
@@ -536,6 +767,7 @@ mod tests {
             name: "test.txt".to_string(),
             description: Some("A test file".to_string()),
             mime_type: Some("text/plain".to_string()),
+            extra: Default::default(),
         };
 
         let json_str = serde_json::to_string(&resource).unwrap();
@@ -608,12 +840,82 @@ mod tests {
     fn test_read_resource_params() {
         let params = ReadResourceParams {
             uri: "file:///test.txt".to_string(),
+            extra: serde_json::Map::new(),
         };
 
         let json_str = serde_json::to_string(&params).unwrap();
         let parsed: ReadResourceParams = serde_json::from_str(&json_str).unwrap();
 
         assert_eq!(parsed.uri, "file:///test.txt");
+        assert!(parsed.extra.is_empty());
+    }
+
+    #[test]
+    fn test_read_resource_params_extra() {
+        let mut extra = serde_json::Map::new();
+        extra.insert("range".to_string(), json!({"start": 0, "end": 1024}));
+        let params = ReadResourceParams {
+            uri: "file:///big.log".to_string(),
+            extra,
+        };
+
+        assert_eq!(
+            serde_json::to_value(&params).unwrap(),
+            json!({"uri": "file:///big.log", "range": {"start": 0, "end": 1024}})
+        );
+    }
+
+    #[test]
+    fn test_tool_full_round_trip() {
+        let json_str = r#"{
+            "name": "get_weather",
+            "title": "Weather Lookup",
+            "description": "Get the weather",
+            "inputSchema": {"type": "object", "properties": {"city": {"type": "string"}}},
+            "outputSchema": {"type": "object", "properties": {"temp": {"type": "number"}}},
+            "annotations": {"readOnlyHint": true, "openWorldHint": true}
+        }"#;
+
+        let tool: Tool = serde_json::from_str(json_str).unwrap();
+        assert_eq!(tool.title.as_deref(), Some("Weather Lookup"));
+        assert_eq!(
+            tool.output_schema.as_ref().unwrap()["properties"]["temp"]["type"],
+            "number"
+        );
+        assert_eq!(tool.hint("readOnlyHint"), Some(true));
+
+        let value = serde_json::to_value(&tool).unwrap();
+        assert_eq!(value, serde_json::from_str::<Value>(json_str).unwrap());
+    }
+
+    #[test]
+    fn test_tool_optional_fields_omitted() {
+        let tool: Tool = serde_json::from_str(r#"{"name": "echo", "inputSchema": {}}"#).unwrap();
+        assert!(tool.title.is_none());
+        assert!(tool.output_schema.is_none());
+
+        let value = serde_json::to_value(&tool).unwrap();
+        assert!(value.get("title").is_none());
+        assert!(value.get("outputSchema").is_none());
+        assert!(value.get("annotations").is_none());
+    }
+
+    #[test]
+    fn test_tool_annotation_hints() {
+        let json_str = r#"{
+            "name": "delete_file",
+            "inputSchema": {"type": "object"},
+            "annotations": {"destructiveHint": true, "readOnlyHint": false}
+        }"#;
+
+        let tool: Tool = serde_json::from_str(json_str).unwrap();
+        assert_eq!(tool.hint("destructiveHint"), Some(true));
+        assert_eq!(tool.hint("readOnlyHint"), Some(false));
+        assert_eq!(tool.hint("idempotentHint"), None);
+
+        let tool: Tool = serde_json::from_str(r#"{"name": "echo", "inputSchema": {}}"#).unwrap();
+        assert!(tool.annotations.is_none());
+        assert_eq!(tool.hint("destructiveHint"), None);
     }
 
     #[test]
@@ -621,8 +923,12 @@ mod tests {
         let result = ListToolsResult {
             tools: vec![Tool {
                 name: "tool1".to_string(),
+                title: None,
                 description: None,
                 input_schema: json!({}),
+                output_schema: None,
+                annotations: None,
+                extra: Default::default(),
             }],
         };
 
@@ -641,11 +947,13 @@ mod tests {
             capabilities: ClientCapabilities {
                 roots: None,
                 sampling: None,
+                extra: Default::default(),
             },
             client_info: Implementation {
                 name: "test".to_string(),
                 version: "1.0".to_string(),
             },
+            meta: None,
         };
 
         let json_str = serde_json::to_string(&params).unwrap();
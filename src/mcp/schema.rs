@@ -0,0 +1,286 @@
+use serde_json::Value;
+use tracing::warn;
+
+/// How many `$ref`s deep to follow, so recursive schemas stay finite.
+const MAX_REF_DEPTH: usize = 8;
+
+/// Check `value` against a JSON Schema, returning a description of each
+/// mismatch found.
+///
+/// Only the commonly used keywords are understood: `type`, `enum`,
+/// `required`, `properties` and `items`. Anything else in the schema is
+/// ignored, so an empty result means "no problems found" rather than a
+/// guarantee of validity.
+pub fn validate(schema: &Value, value: &Value) -> Vec<String> {
+    let mut errors = Vec::new();
+    validate_at(schema, value, "$", &mut errors);
+    errors
+}
+
+/// Replace local `$ref`s (`#/$defs/...`, `#/definitions/...` or any other
+/// JSON pointer into the schema itself) with the schema they point to.
+/// Keywords next to a `$ref`, such as `description`, are kept. External and
+/// unresolvable refs are left as they are, with a warning.
+pub fn resolve_refs(schema: &Value) -> Value {
+    resolve_at(schema, schema, 0)
+}
+
+fn resolve_at(root: &Value, node: &Value, depth: usize) -> Value {
+    match node {
+        Value::Object(map) => {
+            if let Some(Value::String(reference)) = map.get("$ref") {
+                match reference
+                    .strip_prefix('#')
+                    .map(|pointer| root.pointer(pointer))
+                {
+                    Some(Some(target)) if depth < MAX_REF_DEPTH => {
+                        let mut resolved = resolve_at(root, target, depth + 1);
+                        if let Value::Object(resolved_map) = &mut resolved {
+                            for (key, value) in map.iter().filter(|(key, _)| *key != "$ref") {
+                                resolved_map.insert(key.clone(), resolve_at(root, value, depth));
+                            }
+                        }
+                        return resolved;
+                    }
+                    Some(Some(_)) => {
+                        warn!("Not following $ref {} past depth {}", reference, depth)
+                    }
+                    Some(None) => warn!("Schema $ref {} doesn't point into the schema", reference),
+                    None => warn!("Ignoring external schema $ref {}", reference),
+                }
+            }
+            Value::Object(
+                map.iter()
+                    .map(|(key, value)| (key.clone(), resolve_at(root, value, depth)))
+                    .collect(),
+            )
+        }
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|value| resolve_at(root, value, depth))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+fn validate_at(schema: &Value, value: &Value, path: &str, errors: &mut Vec<String>) {
+    if let Some(expected) = schema.get("type") {
+        let types: Vec<&str> = match expected {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(ts) => ts.iter().filter_map(|t| t.as_str()).collect(),
+            _ => Vec::new(),
+        };
+        if !types.is_empty() && !types.iter().any(|t| matches_type(t, value)) {
+            errors.push(format!(
+                "{}: expected {}, got {}",
+                path,
+                types.join(" or "),
+                type_name(value)
+            ));
+            return;
+        }
+    }
+
+    if let Some(options) = schema.get("enum").and_then(|e| e.as_array()) {
+        // Numbers compare by value, so `2.0` is allowed by `[1, 2, 3]`
+        let allowed = options.iter().any(|option| {
+            option == value
+                || matches!((option.as_f64(), value.as_f64()), (Some(a), Some(b)) if a == b)
+        });
+        if !allowed {
+            errors.push(format!(
+                "{}: {} is not one of the allowed values",
+                path, value
+            ));
+        }
+    }
+
+    match value {
+        Value::Object(object) => {
+            if let Some(required) = schema.get("required").and_then(|r| r.as_array()) {
+                for name in required.iter().filter_map(|n| n.as_str()) {
+                    if !object.contains_key(name) {
+                        errors.push(format!("{}: missing required property '{}'", path, name));
+                    }
+                }
+            }
+
+            if let Some(properties) = schema.get("properties").and_then(|p| p.as_object()) {
+                for (name, property_schema) in properties {
+                    if let Some(property) = object.get(name) {
+                        let property_path = format!("{}.{}", path, name);
+                        validate_at(property_schema, property, &property_path, errors);
+                    }
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    validate_at(item_schema, item, &format!("{}[{}]", path, i), errors);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn matches_type(expected: &str, value: &Value) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        "number" => value.is_number(),
+        // A number with no fractional part counts, e.g. the `2.0` Python
+        // servers often send
+        "integer" => {
+            value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|n| n.fract() == 0.0)
+        }
+        // Unknown type names can't be checked
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_resolve_local_refs() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "point": {"$ref": "#/$defs/point", "description": "Where"},
+                "unit": {"$ref": "#/definitions/unit"}
+            },
+            "$defs": {
+                "point": {
+                    "type": "object",
+                    "properties": {"x": {"$ref": "#/$defs/coord"}}
+                },
+                "coord": {"type": "number"}
+            },
+            "definitions": {"unit": {"type": "string", "enum": ["m", "ft"]}}
+        });
+
+        let resolved = resolve_refs(&schema);
+        assert_eq!(
+            resolved["properties"]["point"],
+            json!({
+                "type": "object",
+                "properties": {"x": {"type": "number"}},
+                "description": "Where"
+            })
+        );
+        assert_eq!(
+            resolved["properties"]["unit"],
+            json!({"type": "string", "enum": ["m", "ft"]})
+        );
+    }
+
+    #[test]
+    fn test_resolve_refs_leaves_external_and_stops_on_recursion() {
+        let schema = json!({
+            "properties": {
+                "remote": {"$ref": "https://example.com/schema.json"},
+                "missing": {"$ref": "#/$defs/nope"},
+                "tree": {"$ref": "#/$defs/node"}
+            },
+            "$defs": {
+                "node": {"type": "object", "properties": {"child": {"$ref": "#/$defs/node"}}}
+            }
+        });
+
+        let resolved = resolve_refs(&schema);
+        assert_eq!(
+            resolved["properties"]["remote"],
+            json!({"$ref": "https://example.com/schema.json"})
+        );
+        assert_eq!(
+            resolved["properties"]["missing"],
+            json!({"$ref": "#/$defs/nope"})
+        );
+        assert_eq!(resolved["properties"]["tree"]["type"], "object");
+    }
+
+    #[test]
+    fn test_validate_matching_value() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "temp": {"type": "number"},
+                "tags": {"type": "array", "items": {"type": "string"}}
+            },
+            "required": ["temp"]
+        });
+
+        let errors = validate(&schema, &json!({"temp": 21, "tags": ["sunny"]}));
+        assert!(errors.is_empty(), "{:?}", errors);
+    }
+
+    #[test]
+    fn test_validate_reports_missing_and_mistyped() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "temp": {"type": "number"},
+                "tags": {"type": "array", "items": {"type": "string"}}
+            },
+            "required": ["temp", "unit"]
+        });
+
+        let errors = validate(&schema, &json!({"temp": "warm", "tags": ["a", 2]}));
+        assert_eq!(
+            errors,
+            vec![
+                "$: missing required property 'unit'",
+                "$.tags[1]: expected string, got integer",
+                "$.temp: expected number, got string",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_integer_and_enum() {
+        let schema = json!({"type": "integer", "enum": [1, 2, 3]});
+
+        assert!(validate(&schema, &json!(2)).is_empty());
+        assert!(validate(&schema, &json!(2.0)).is_empty());
+        assert_eq!(
+            validate(&schema, &json!(2.5)),
+            vec!["$: expected integer, got number"]
+        );
+        assert_eq!(
+            validate(&schema, &json!(4)),
+            vec!["$: 4 is not one of the allowed values"]
+        );
+    }
+
+    #[test]
+    fn test_validate_type_list() {
+        let schema = json!({"type": ["string", "null"]});
+
+        assert!(validate(&schema, &json!(null)).is_empty());
+        assert_eq!(
+            validate(&schema, &json!(true)),
+            vec!["$: expected string or null, got boolean"]
+        );
+    }
+}
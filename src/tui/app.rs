@@ -1,12 +1,24 @@
-use crate::logging::LogEntry;
+use super::cache::{CachedRequest, CachedResult, ResultCache};
+use super::settings::Settings;
+use crate::logging::{redact, LogEntry};
 use crate::mcp::protocol::*;
-use crate::mcp::McpClient;
+use crate::mcp::schema;
+use crate::mcp::{McpClient, ServerLists};
 use anyhow::Result;
-use serde::Serialize;
+use base64::prelude::*;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{info, warn, Level};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Tab {
     Tools,
     Prompts,
@@ -17,37 +29,34 @@ pub enum Tab {
 }
 
 impl Tab {
-    pub fn next(&self, debug_mode: bool) -> Self {
-        match self {
-            Tab::Tools => Tab::Prompts,
-            Tab::Prompts => Tab::Resources,
-            Tab::Resources => Tab::ServerInfo,
-            Tab::ServerInfo => Tab::ServerLogs,
-            Tab::ServerLogs => {
-                if debug_mode {
-                    Tab::DebugLogs
-                } else {
-                    Tab::Tools
-                }
-            }
-            Tab::DebugLogs => Tab::Tools,
+    /// The tabs shown by default, in order.
+    pub fn defaults(debug_mode: bool) -> Vec<Tab> {
+        let mut tabs = vec![
+            Tab::Tools,
+            Tab::Prompts,
+            Tab::Resources,
+            Tab::ServerInfo,
+            Tab::ServerLogs,
+        ];
+        if debug_mode {
+            tabs.push(Tab::DebugLogs);
         }
+        tabs
     }
 
-    pub fn previous(&self, debug_mode: bool) -> Self {
-        match self {
-            Tab::Tools => {
-                if debug_mode {
-                    Tab::DebugLogs
-                } else {
-                    Tab::ServerLogs
-                }
-            }
-            Tab::Prompts => Tab::Tools,
-            Tab::Resources => Tab::Prompts,
-            Tab::ServerInfo => Tab::Resources,
-            Tab::ServerLogs => Tab::ServerInfo,
-            Tab::DebugLogs => Tab::ServerLogs,
+    /// The tab after this one in `tabs`, wrapping around.
+    pub fn next(&self, tabs: &[Tab]) -> Self {
+        match tabs.iter().position(|t| t == self) {
+            Some(i) => tabs[(i + 1) % tabs.len()],
+            None => tabs.first().copied().unwrap_or(*self),
+        }
+    }
+
+    /// The tab before this one in `tabs`, wrapping around.
+    pub fn previous(&self, tabs: &[Tab]) -> Self {
+        match tabs.iter().position(|t| t == self) {
+            Some(i) => tabs[(i + tabs.len() - 1) % tabs.len()],
+            None => tabs.first().copied().unwrap_or(*self),
         }
     }
 
@@ -63,25 +72,134 @@ impl Tab {
     }
 }
 
+impl std::str::FromStr for Tab {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "tools" => Ok(Tab::Tools),
+            "prompts" => Ok(Tab::Prompts),
+            "resources" => Ok(Tab::Resources),
+            "info" | "server-info" => Ok(Tab::ServerInfo),
+            "logs" | "server-logs" => Ok(Tab::ServerLogs),
+            "debug" | "debug-logs" => Ok(Tab::DebugLogs),
+            _ => Err(format!(
+                "unknown tab '{}' (expected tools, prompts, resources, info, logs or debug)",
+                s
+            )),
+        }
+    }
+}
+
+/// Column the tools table is sorted by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ToolSort {
+    Name,
+    // Destructive tools first, then read-only, then idempotent
+    Annotations,
+    // Most required parameters first
+    Required,
+    Description,
+    // Most recently called first, then the server's order
+    Recent,
+}
+
+impl ToolSort {
+    /// The next column to sort by, or `None` for the server's order.
+    pub fn cycle(sort: Option<ToolSort>) -> Option<ToolSort> {
+        match sort {
+            None => Some(ToolSort::Name),
+            Some(ToolSort::Name) => Some(ToolSort::Annotations),
+            Some(ToolSort::Annotations) => Some(ToolSort::Required),
+            Some(ToolSort::Required) => Some(ToolSort::Description),
+            Some(ToolSort::Description) => None,
+            // Not a column; start over from the first one
+            Some(ToolSort::Recent) => Some(ToolSort::Name),
+        }
+    }
+}
+
+/// How `export_logs` writes the logs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogExportFormat {
+    /// One indented JSON document
+    #[default]
+    Pretty,
+    /// The same document on a single line
+    Compact,
+    /// A JSON object per log line, for streaming tools like `jq`
+    Jsonl,
+}
+
+impl std::str::FromStr for LogExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "pretty" => Ok(LogExportFormat::Pretty),
+            "compact" => Ok(LogExportFormat::Compact),
+            "jsonl" => Ok(LogExportFormat::Jsonl),
+            _ => Err(format!(
+                "unknown log export format '{}' (expected pretty, compact or jsonl)",
+                s
+            )),
+        }
+    }
+}
+
 pub struct App {
     pub current_tab: Tab,
+    // Tabs shown, in order
+    pub tabs: Vec<Tab>,
+    // Full keybinding help overlay
+    pub show_help: bool,
+    pub help_scroll: usize,
+    // Server command line, for the help overlay
+    pub connection_target: String,
     pub tools: Vec<Tool>,
     pub prompts: Vec<Prompt>,
     pub resources: Vec<Resource>,
     pub server_info: Option<InitializeResult>,
+    // What mcpeek sent with `initialize`
+    pub client_params: Option<InitializeParams>,
+    // Directories given with `--root`
+    pub roots: Vec<Root>,
     pub logs: Vec<String>,
     pub debug_logs: Vec<LogEntry>,
     pub debug_mode: bool,
     pub selected_tool: usize,
     pub selected_prompt: usize,
     pub selected_resource: usize,
+    // List viewport offsets, written back by the renderer so each tab keeps
+    // its own scroll position across frames and tab switches
+    pub tool_list_offset: Cell<usize>,
+    pub prompt_list_offset: Cell<usize>,
+    pub resource_list_offset: Cell<usize>,
     pub log_scroll: usize,
     pub debug_log_scroll: usize,
+    // Keep each logs tab scrolled to the newest lines as they arrive
+    pub log_follow: bool,
+    pub debug_log_follow: bool,
+    // Scroll positions that show the last log rows, written back by the
+    // renderer since they depend on the view size
+    pub log_bottom: Cell<usize>,
+    pub debug_log_bottom: Cell<usize>,
+    // Wrapped row each log line starts on, also written back by the renderer
+    pub log_rows: RefCell<Vec<usize>>,
+    pub debug_log_rows: RefCell<Vec<usize>>,
+    // Log line the last N/P jump landed on, highlighted until the view moves
+    pub problem_mark: Option<(Tab, usize)>,
     pub detail_scroll: usize,
     pub server_info_scroll: usize,
     pub loading: bool,
     pub error_message: Option<String>,
-    pub detail_view: Option<String>,
+    pub detail_view: Option<DetailView>,
+    // The detail view describes the current tab's selected item (or the
+    // server), rather than showing a result
+    item_detail: bool,
+    // That description is the raw JSON the server sent
+    pub raw_item_json: bool,
     pub should_quit: bool,
     // Tool calling state
     pub tool_call_input_mode: bool,
@@ -90,125 +208,804 @@ pub struct App {
     pub input_field_index: usize,
     pub input_fields: Vec<InputField>,
     pub tool_input_scroll: usize,
+    pub raw_json_mode: bool,
+    pub raw_json_input: String,
+    pub raw_json_cursor: usize,
+    // Error from the last failed submission, shown inline above the fields
+    pub tool_call_error: Option<String>,
+    // Name of the field the error refers to, when it can be worked out
+    pub tool_call_error_field: Option<String>,
+    // Cursor (as a character index) of each form field, keyed by field name
+    pub input_cursors: HashMap<String, usize>,
     // Prompt input state
     pub prompt_input_mode: bool,
     pub prompt_inputs: HashMap<String, String>,
     pub prompt_result: Option<GetPromptResult>,
+    // URIs of resources embedded in the prompt result being shown
+    pub embedded_resources: Vec<String>,
+    // JSON object found in the prompt result being shown, to use as tool arguments
+    pub prompt_json_arguments: Option<serde_json::Map<String, Value>>,
     // Resource read state
     pub resource_read_result: Option<Vec<ResourceContents>>,
+    // Name and URI the result was read from, for re-rendering it
+    resource_read_source: Option<(String, String)>,
+    // Single-line text prompt state
+    pub text_prompt: Option<TextPrompt>,
+    // Confirmation before calling destructive tools
+    pub confirm_destructive: bool,
+    pub confirm_patterns: Vec<String>,
+    pub tool_confirmation: Option<ToolConfirmation>,
+    // Send list requests as a single JSON-RPC batch
+    pub batch_requests: bool,
+    pub log_export_format: LogExportFormat,
+    // Pause after the handshake before the first list requests
+    pub ready_delay: Duration,
+    // Results of read-only requests, kept when `--cache` is on
+    pub result_cache: Option<ResultCache>,
+    // Request whose result the running task should add to the cache
+    pending_cache_request: Option<CachedRequest>,
+    // Set while the detail view shows a cached result, so it can be refreshed
+    pub cached_request: Option<CachedRequest>,
+    // Draw with modifiers only (`NO_COLOR` or `--no-color`)
+    pub no_color: bool,
+    // Matches masked in results and logs (`--redact-pattern`)
+    pub redact_patterns: Vec<Regex>,
+    // When the session started, for the uptime shown with the server info
+    pub connected_at: Instant,
+    // When each tab's list (or the server info) was last loaded
+    pub last_loaded: HashMap<Tab, Instant>,
+    // Set once the server stops answering (exited, or closed its stdin)
+    pub disconnected: bool,
+    // View preferences saved across runs
+    pub settings: Settings,
+    // Width to cap wrapping to when the cap is turned back on
+    pub last_wrap_width: u16,
+    // Form to open when the tools first arrive
+    pub initial_tool_call: Option<InitialToolCall>,
+    // Set when the `--once` form is ready to be executed
+    run_initial_tool_call: bool,
+    // Tool names in the server's order, to undo sorting
+    listed_tool_names: Vec<String>,
+    // Required parameter counts by tool name, worked out once per load since
+    // resolving a schema can log warnings about its refs
+    required_params: HashMap<String, usize>,
+    // Only tools matching one of these are shown (`--select`)
+    pub tool_select: Vec<Regex>,
+    // How many listed tools `--select` left out
+    pub unselected_tools: usize,
+    // When each tool was last called this session
+    tool_last_used: HashMap<String, Instant>,
+    // Background request state
+    pub pending_task: Option<PendingTask>,
+    pending_load: Option<PendingLoad>,
+    // The startup lists, kept apart so switching tabs doesn't cancel them
+    pending_preload: Option<PendingLoad>,
+    next_task_id: u64,
+    task_tx: mpsc::UnboundedSender<TaskMessage>,
+    task_rx: mpsc::UnboundedReceiver<TaskMessage>,
+}
+
+/// What a submitted single-line text prompt is applied to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextPromptKind {
+    DebugLogFilter,
+    ResourceUri,
+}
+
+/// Wrap width the `L` key caps to when none was given or saved.
+const DEFAULT_WRAP_WIDTH: u16 = 100;
+
+/// Wait before asking again when a startup list fails or comes back empty.
+const LIST_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Results larger than this are truncated in the detail view.
+const DETAIL_TRUNCATE_BYTES: usize = 256 * 1024;
+
+/// Text shown in the detail view, split into lines once up front so each
+/// frame only has to wrap the lines on screen.
+#[derive(Debug, Clone)]
+pub struct DetailView {
+    lines: Vec<String>,
+    // The complete text as given, which `lines` may have cut short or split
+    text: String,
+    truncated: bool,
+    // Parts of `lines` that can be collapsed to their title, in order
+    sections: Vec<DetailSection>,
+    // Indexes of the lines shown while any section is collapsed
+    shown: Option<Vec<usize>>,
+}
+
+#[derive(Debug, Clone)]
+struct DetailSection {
+    // Line holding the title; the body runs up to `end`
+    title: usize,
+    end: usize,
+    collapsed: bool,
+}
+
+/// Text for `DetailView::sectioned`: plain, or a section that can be
+/// collapsed to its title line.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DetailPart {
+    Text(String),
+    Section { title: String, body: String },
+}
+
+impl DetailPart {
+    /// The part as it appears in the detail text: a section is its title
+    /// line, then its body and a blank line.
+    fn text(&self) -> String {
+        match self {
+            DetailPart::Text(text) => text.clone(),
+            DetailPart::Section { title, body } => {
+                format!("{}\n{}\n\n", title, body.trim_end_matches('\n'))
+            }
+        }
+    }
+}
+
+/// A line of the detail view as displayed.
+pub struct DetailRow<'a> {
+    pub text: &'a str,
+    // Whether the section this line is the title of is collapsed
+    pub section: Option<bool>,
+}
+
+impl DetailView {
+    /// Show `text`, truncating it if it's too large to display comfortably.
+    pub fn new(text: String) -> Self {
+        if text.len() <= DETAIL_TRUNCATE_BYTES {
+            return Self::full(text);
+        }
+
+        let mut end = DETAIL_TRUNCATE_BYTES;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        let mut lines = split_detail_lines(&text[..end]);
+        lines.push(String::new());
+        lines.push(format!(
+            "(truncated: showing {} KB of {} KB - press F to view in full or W to save to a file)",
+            end / 1024,
+            text.len() / 1024
+        ));
+        Self {
+            lines,
+            text,
+            truncated: true,
+            sections: Vec::new(),
+            shown: None,
+        }
+    }
+
+    /// Show all of `text`, however large.
+    pub fn full(text: String) -> Self {
+        Self {
+            lines: split_detail_lines(&text),
+            text,
+            truncated: false,
+            sections: Vec::new(),
+            shown: None,
+        }
+    }
+
+    /// Show `parts` with each section's body under its title line, ready to
+    /// be collapsed. Text too large to show in full loses its sections and
+    /// is truncated as usual.
+    pub fn sectioned(parts: Vec<DetailPart>) -> Self {
+        let texts: Vec<String> = parts.iter().map(DetailPart::text).collect();
+        if texts.iter().map(String::len).sum::<usize>() > DETAIL_TRUNCATE_BYTES {
+            return Self::new(texts.concat());
+        }
+
+        let mut lines = Vec::new();
+        let mut sections = Vec::new();
+        for (part, text) in parts.iter().zip(&texts) {
+            let start = lines.len();
+            lines.extend(split_detail_lines(text));
+            if let DetailPart::Section { .. } = part {
+                sections.push(DetailSection {
+                    title: start,
+                    end: lines.len(),
+                    collapsed: false,
+                });
+            }
+        }
+        Self {
+            lines,
+            text: texts.concat(),
+            truncated: false,
+            sections,
+            shown: None,
+        }
+    }
+
+    /// Number of lines shown, leaving out the bodies of collapsed sections.
+    pub fn line_count(&self) -> usize {
+        self.shown.as_ref().map_or(self.lines.len(), Vec::len)
+    }
+
+    /// The lines shown from `start` up to `end`.
+    pub fn rows(&self, start: usize, end: usize) -> Vec<DetailRow<'_>> {
+        (start..end.min(self.line_count()))
+            .map(|row| {
+                let line = self.line_at(row);
+                DetailRow {
+                    text: &self.lines[line],
+                    section: self
+                        .sections
+                        .iter()
+                        .find(|section| section.title == line)
+                        .map(|section| section.collapsed),
+                }
+            })
+            .collect()
+    }
+
+    pub fn has_sections(&self) -> bool {
+        !self.sections.is_empty()
+    }
+
+    /// Rows of the section titles as shown, in order.
+    pub fn section_rows(&self) -> Vec<usize> {
+        self.sections
+            .iter()
+            .map(|section| self.row_of(section.title))
+            .collect()
+    }
+
+    /// Collapse or expand the section shown at `row` (or the last one above
+    /// it), returning the row of its title afterwards.
+    pub fn toggle_section(&mut self, row: usize) -> Option<usize> {
+        let line = self.line_at(row.min(self.line_count().saturating_sub(1)));
+        let section = self
+            .sections
+            .iter_mut()
+            .rev()
+            .find(|section| section.title <= line)?;
+        section.collapsed = !section.collapsed;
+        let title = section.title;
+        self.update_shown();
+        Some(self.row_of(title))
+    }
+
+    /// Collapse every section, or expand them all when none is expanded.
+    pub fn toggle_all_sections(&mut self) {
+        let collapse = self.sections.iter().any(|section| !section.collapsed);
+        for section in &mut self.sections {
+            section.collapsed = collapse;
+        }
+        self.update_shown();
+    }
+
+    fn update_shown(&mut self) {
+        if !self.sections.iter().any(|section| section.collapsed) {
+            self.shown = None;
+            return;
+        }
+        let hidden = |line: usize| {
+            self.sections
+                .iter()
+                .any(|s| s.collapsed && s.title < line && line < s.end)
+        };
+        self.shown = Some((0..self.lines.len()).filter(|&l| !hidden(l)).collect());
+    }
+
+    // Index into `lines` of the line shown at `row`
+    fn line_at(&self, row: usize) -> usize {
+        match &self.shown {
+            Some(shown) => shown[row],
+            None => row,
+        }
+    }
+
+    // Row a shown line is displayed at
+    fn row_of(&self, line: usize) -> usize {
+        match &self.shown {
+            Some(shown) => shown.partition_point(|&l| l < line),
+            None => line,
+        }
+    }
+
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// The complete text, including anything cut off by truncation.
+    pub fn text(&self) -> String {
+        self.text.clone()
+    }
+}
+
+/// Split detail text into lines, breaking up very long ones (e.g. minified
+/// JSON) so no single line is expensive to wrap.
+fn split_detail_lines(text: &str) -> Vec<String> {
+    const MAX_LINE_CHARS: usize = 4096;
+
+    let mut lines = Vec::new();
+    for line in text.lines() {
+        if line.len() <= MAX_LINE_CHARS {
+            lines.push(line.to_string());
+            continue;
+        }
+        let chars: Vec<char> = line.chars().collect();
+        lines.extend(
+            chars
+                .chunks(MAX_LINE_CHARS)
+                .map(|chunk| chunk.iter().collect()),
+        );
+    }
+    lines
+}
+
+/// Where an input form popup goes: its size, and the rows an inline error
+/// takes at the top of it.
+pub struct InputPopup {
+    pub width: u16,
+    pub height: u16,
+    pub error_rows: u16,
+}
+
+/// A single-line text input overlay, e.g. for entering a filter.
+#[derive(Debug, Clone)]
+pub struct TextPrompt {
+    pub kind: TextPromptKind,
+    pub title: String,
+    pub value: String,
+    pub cursor: usize,
+}
+
+/// Tool call form to open once the tools are listed (`--tool` / `--arg`).
+#[derive(Debug, Clone)]
+pub struct InitialToolCall {
+    pub tool_name: String,
+    pub arguments: Vec<(String, String)>,
+    // Call the tool as soon as the form is filled (`--once`)
+    pub execute: bool,
+}
+
+/// A tool call held back until the user confirms it.
+#[derive(Debug, Clone)]
+pub struct ToolConfirmation {
+    pub tool_name: String,
+    pub reason: String,
+    arguments: Option<HashMap<String, Value>>,
+}
+
+/// A tool call, prompt get or resource read running on a spawned task.
+pub struct PendingTask {
+    pub id: u64,
+    pub description: String,
+    pub started: Instant,
+    handle: JoinHandle<()>,
+}
+
+/// A tab's list request running on a spawned task.
+struct PendingLoad {
+    id: u64,
+    handle: JoinHandle<()>,
+}
+
+/// Outcome of a background request, delivered back to the event loop.
+pub enum TaskOutcome {
+    ListsLoaded(ServerLists),
+    ToolsLoaded(Result<Vec<Tool>>),
+    PromptsLoaded(Result<Vec<Prompt>>),
+    ResourcesLoaded(Result<Vec<Resource>>),
+    ServerInfoLoaded(Option<InitializeResult>),
+    Reinitialized {
+        result: Result<InitializeResult>,
+        // Lists reloaded after a successful handshake, as capabilities may
+        // have changed
+        lists: Option<ServerLists>,
+    },
+    ToolCall {
+        tool_name: String,
+        result: Result<CallToolResult>,
+    },
+    PromptGet {
+        prompt_name: String,
+        result: Result<GetPromptResult>,
+    },
+    ResourceRead {
+        resource_name: String,
+        uri: String,
+        result: Result<Vec<ResourceContents>>,
+    },
+}
+
+pub struct TaskMessage {
+    id: u64,
+    outcome: TaskOutcome,
 }
 
 #[derive(Debug, Clone)]
 pub struct InputField {
     pub name: String,
     pub field_type: String,
+    // Item type of an array of primitives, which is entered as a comma-separated list
+    pub item_type: Option<String>,
     pub required: bool,
     pub description: Option<String>,
 }
 
 impl App {
     pub fn new(debug_mode: bool) -> Self {
+        let (task_tx, task_rx) = mpsc::unbounded_channel();
+
         Self {
             current_tab: Tab::Tools,
+            tabs: Tab::defaults(debug_mode),
+            show_help: false,
+            help_scroll: 0,
+            connection_target: String::new(),
             tools: Vec::new(),
             prompts: Vec::new(),
             resources: Vec::new(),
             server_info: None,
+            client_params: None,
+            roots: Vec::new(),
             logs: Vec::new(),
             debug_logs: Vec::new(),
             debug_mode,
             selected_tool: 0,
             selected_prompt: 0,
             selected_resource: 0,
+            tool_list_offset: Cell::new(0),
+            prompt_list_offset: Cell::new(0),
+            resource_list_offset: Cell::new(0),
             log_scroll: 0,
             debug_log_scroll: 0,
+            log_follow: true,
+            debug_log_follow: true,
+            log_bottom: Cell::new(0),
+            debug_log_bottom: Cell::new(0),
+            log_rows: RefCell::new(Vec::new()),
+            debug_log_rows: RefCell::new(Vec::new()),
+            problem_mark: None,
             detail_scroll: 0,
             server_info_scroll: 0,
             loading: true,
             error_message: None,
             detail_view: None,
+            item_detail: false,
+            raw_item_json: false,
             should_quit: false,
             tool_call_input_mode: false,
             tool_call_inputs: HashMap::new(),
             tool_call_result: None,
             input_field_index: 0,
+            tool_call_error: None,
+            tool_call_error_field: None,
             input_fields: Vec::new(),
             tool_input_scroll: 0,
+            raw_json_mode: false,
+            raw_json_input: String::new(),
+            raw_json_cursor: 0,
+            input_cursors: HashMap::new(),
             prompt_input_mode: false,
             prompt_inputs: HashMap::new(),
             prompt_result: None,
+            embedded_resources: Vec::new(),
+            prompt_json_arguments: None,
             resource_read_result: None,
+            resource_read_source: None,
+            text_prompt: None,
+            confirm_destructive: true,
+            confirm_patterns: Vec::new(),
+            tool_confirmation: None,
+            batch_requests: true,
+            log_export_format: LogExportFormat::default(),
+            ready_delay: Duration::ZERO,
+            result_cache: None,
+            pending_cache_request: None,
+            cached_request: None,
+            no_color: false,
+            redact_patterns: Vec::new(),
+            connected_at: Instant::now(),
+            last_loaded: HashMap::new(),
+            disconnected: false,
+            settings: Settings::default(),
+            last_wrap_width: DEFAULT_WRAP_WIDTH,
+            initial_tool_call: None,
+            run_initial_tool_call: false,
+            listed_tool_names: Vec::new(),
+            required_params: HashMap::new(),
+            tool_select: Vec::new(),
+            unselected_tools: 0,
+            tool_last_used: HashMap::new(),
+            pending_task: None,
+            pending_load: None,
+            pending_preload: None,
+            next_task_id: 0,
+            task_tx,
+            task_rx,
         }
     }
 
-    pub async fn load_data(&mut self, client: &McpClient) -> Result<()> {
-        self.loading = true;
-        self.error_message = None;
+    /// Show only `tabs`, in the given order. The debug log tab is dropped
+    /// unless debug mode is on, and the defaults stay if nothing is left.
+    pub fn set_tabs(&mut self, tabs: &[Tab]) {
+        let mut enabled = Vec::new();
+        for tab in tabs {
+            if (*tab != Tab::DebugLogs || self.debug_mode) && !enabled.contains(tab) {
+                enabled.push(*tab);
+            }
+        }
+        if let Some(first) = enabled.first() {
+            self.current_tab = *first;
+            self.tabs = enabled;
+        }
+    }
+
+    pub fn next_tab(&mut self) {
+        self.current_tab = self.current_tab.next(&self.tabs);
+        info!(
+            action = "switch_tab",
+            "Switched to the {} tab",
+            self.current_tab.as_str()
+        );
+    }
+
+    pub fn previous_tab(&mut self) {
+        self.current_tab = self.current_tab.previous(&self.tabs);
+        info!(
+            action = "switch_tab",
+            "Switched to the {} tab",
+            self.current_tab.as_str()
+        );
+    }
+
+    /// Sort the tools by the next column, keeping the same tool selected.
+    pub fn cycle_tool_sort(&mut self) {
+        self.set_tool_sort(ToolSort::cycle(self.settings.tool_sort));
+    }
+
+    /// Turn the wrap width cap on or off.
+    pub fn toggle_wrap_width(&mut self) {
+        self.settings.wrap_width = match self.settings.wrap_width {
+            Some(width) => {
+                self.last_wrap_width = width;
+                None
+            }
+            None => Some(self.last_wrap_width),
+        };
+    }
+
+    /// Switch between the server's order and most recently called first.
+    pub fn toggle_recent_tools(&mut self) {
+        if self.settings.tool_sort == Some(ToolSort::Recent) {
+            self.set_tool_sort(None);
+        } else {
+            self.set_tool_sort(Some(ToolSort::Recent));
+        }
+    }
+
+    fn set_tool_sort(&mut self, sort: Option<ToolSort>) {
+        self.settings.tool_sort = sort;
+        self.resort_tools();
+    }
+
+    /// Re-sort the tools, keeping the same tool selected.
+    fn resort_tools(&mut self) {
+        let previous = self.tools.get(self.selected_tool).map(|t| t.name.clone());
+        self.sort_tools();
+        self.selected_tool = reselect(&self.tools, previous.as_deref(), self.selected_tool, |t| {
+            &t.name
+        });
+    }
+
+    /// Number of parameters `tool`'s input schema marks as required, as of
+    /// the last time the tools were loaded.
+    pub fn required_param_count(&self, tool: &Tool) -> usize {
+        self.required_params.get(&tool.name).copied().unwrap_or(0)
+    }
+
+    fn sort_tools(&mut self) {
+        match self.settings.tool_sort {
+            None => {
+                let listed = &self.listed_tool_names;
+                self.tools
+                    .sort_by_key(|t| listed.iter().position(|name| *name == t.name));
+            }
+            Some(ToolSort::Name) => self.tools.sort_by_key(|t| t.name.to_lowercase()),
+            Some(ToolSort::Annotations) => self.tools.sort_by_key(|t| {
+                [
+                    t.hint("destructiveHint") != Some(true),
+                    t.hint("readOnlyHint") != Some(true),
+                    t.hint("idempotentHint") != Some(true),
+                ]
+            }),
+            Some(ToolSort::Required) => {
+                let required = &self.required_params;
+                self.tools.sort_by_key(|t| {
+                    std::cmp::Reverse(required.get(&t.name).copied().unwrap_or(0))
+                });
+            }
+            Some(ToolSort::Recent) => {
+                let listed = &self.listed_tool_names;
+                let last_used = &self.tool_last_used;
+                self.tools.sort_by_key(|t| {
+                    let used = last_used.get(&t.name);
+                    (
+                        used.is_none(),
+                        std::cmp::Reverse(used.copied()),
+                        listed.iter().position(|name| *name == t.name),
+                    )
+                });
+            }
+            Some(ToolSort::Description) => self.tools.sort_by_key(|t| {
+                // Tools without a description go last
+                t.description
+                    .as_deref()
+                    .map(str::to_lowercase)
+                    .map_or((true, String::new()), |d| (false, d))
+            }),
+        }
+    }
+
+    /// Whether the server's capabilities show it doesn't support the tab's
+    /// list. False until the server info is known, and for non-list tabs.
+    pub fn lacks_capability(&self, tab: Tab) -> bool {
+        let Some(info) = &self.server_info else {
+            return false;
+        };
+        let capabilities = &info.capabilities;
+        match tab {
+            Tab::Tools => capabilities.tools.is_none(),
+            Tab::Prompts => capabilities.prompts.is_none(),
+            Tab::Resources => capabilities.resources.is_none(),
+            _ => false,
+        }
+    }
+
+    /// Start loading the current tab's data in the background. Any load still
+    /// running for a previously selected tab is cancelled, but not the startup
+    /// lists: while those are on their way, list tabs wait for them instead.
+    pub fn load_data(&mut self, client: &Arc<McpClient>) {
+        if let Some(load) = self.pending_load.take() {
+            load.handle.abort();
+        }
+        self.loading = self.pending_preload.is_some();
+        // Other errors, such as a lost connection, stay up until read
+        let failed = format!(
+            "Failed to load {}:",
+            self.current_tab.as_str().to_lowercase()
+        );
+        if self
+            .error_message
+            .as_deref()
+            .is_some_and(|e| e.starts_with(&failed))
+        {
+            self.error_message = None;
+        }
+        if self.lacks_capability(self.current_tab) {
+            // Asking anyway only gets an error, or a long wait on servers
+            // that ignore unknown methods
+            return;
+        }
+        if self.pending_preload.is_some()
+            && matches!(self.current_tab, Tab::Tools | Tab::Prompts | Tab::Resources)
+        {
+            return;
+        }
+        info!(
+            action = "load",
+            "Loading the {} tab",
+            self.current_tab.as_str()
+        );
 
+        let client = client.clone();
         match self.current_tab {
-            Tab::Tools => match client.list_tools().await {
-                Ok(tools) => {
-                    self.tools = tools;
-                    if self.selected_tool >= self.tools.len() && !self.tools.is_empty() {
-                        self.selected_tool = self.tools.len() - 1;
-                    }
-                }
-                Err(e) => {
-                    self.error_message = Some(format!("Failed to load tools: {}", e));
-                }
-            },
-            Tab::Prompts => match client.list_prompts().await {
-                Ok(prompts) => {
-                    self.prompts = prompts;
-                    if self.selected_prompt >= self.prompts.len() && !self.prompts.is_empty() {
-                        self.selected_prompt = self.prompts.len() - 1;
-                    }
-                }
-                Err(e) => {
-                    self.error_message = Some(format!("Failed to load prompts: {}", e));
-                }
-            },
-            Tab::Resources => match client.list_resources().await {
-                Ok(resources) => {
-                    self.resources = resources;
-                    if self.selected_resource >= self.resources.len() && !self.resources.is_empty()
-                    {
-                        self.selected_resource = self.resources.len() - 1;
-                    }
-                }
-                Err(e) => {
-                    self.error_message = Some(format!("Failed to load resources: {}", e));
-                }
-            },
-            Tab::ServerInfo => {
-                self.server_info = client.get_server_info().await;
+            Tab::Tools => {
+                self.spawn_load(async move { TaskOutcome::ToolsLoaded(client.list_tools().await) })
             }
-            Tab::ServerLogs => {
-                let new_logs = client.get_logs().await;
-                self.logs.extend(new_logs);
+            Tab::Prompts => self
+                .spawn_load(async move { TaskOutcome::PromptsLoaded(client.list_prompts().await) }),
+            Tab::Resources => self.spawn_load(async move {
+                TaskOutcome::ResourcesLoaded(client.list_resources().await)
+            }),
+            Tab::ServerInfo => self.spawn_load(async move {
+                TaskOutcome::ServerInfoLoaded(client.get_server_info().await)
+            }),
+            Tab::ServerLogs | Tab::DebugLogs => {
+                // Logs are polled every tick via update_logs/update_debug_logs
             }
-            Tab::DebugLogs => {
-                // Debug logs are updated separately via update_debug_logs
+        }
+    }
+
+    /// Load the tools, prompts and resources lists up front, batching the
+    /// requests into one round trip unless `batch_requests` is off.
+    ///
+    /// Waits `ready_delay` first, and asks once more for any list that fails
+    /// or is empty, for servers that are still setting up after `initialized`.
+    pub fn preload_lists(&mut self, client: &Arc<McpClient>) {
+        if let Some(load) = self.pending_preload.take() {
+            load.handle.abort();
+        }
+        self.error_message = None;
+
+        info!(
+            action = "load_lists",
+            "Loading the tools, prompts and resources lists"
+        );
+
+        let client = client.clone();
+        let batch = self.batch_requests;
+        let ready_delay = self.ready_delay;
+        let (id, handle) = self.spawn(async move {
+            tokio::time::sleep(ready_delay).await;
+            let mut lists = client.list_all(batch).await;
+            if lists.has_missing() {
+                info!("A list failed or was empty; asking again in case the server wasn't ready");
+                tokio::time::sleep(LIST_RETRY_DELAY).await;
+                lists.fill_from(client.list_all(batch).await);
             }
+            TaskOutcome::ListsLoaded(lists)
+        });
+        self.loading = true;
+        self.pending_preload = Some(PendingLoad { id, handle });
+    }
+
+    /// Re-run the `initialize` handshake on the existing connection, then
+    /// reload the server info and lists.
+    pub fn reinitialize(&mut self, client: &Arc<McpClient>) {
+        if self.reject_if_busy() {
+            return;
         }
 
-        self.loading = false;
-        Ok(())
+        info!(action = "reinitialize", "Re-initializing the server");
+
+        let client = client.clone();
+        let batch = self.batch_requests;
+        self.spawn_task("Re-initializing".to_string(), async move {
+            let result = client.initialize().await;
+            let lists = match result {
+                Ok(_) => Some(client.list_all(batch).await),
+                Err(_) => None,
+            };
+            TaskOutcome::Reinitialized { result, lists }
+        });
     }
 
-    pub async fn update_logs(&mut self, client: &McpClient) {
+    /// Pick up new server stderr output, returning whether there was any.
+    pub async fn update_logs(&mut self, client: &McpClient) -> bool {
         let new_logs = client.get_logs().await;
-        self.logs.extend(new_logs);
+        let changed = !new_logs.is_empty();
+        self.logs.extend(
+            new_logs
+                .into_iter()
+                .map(|line| redact(line, &self.redact_patterns)),
+        );
+        changed
+    }
+
+    /// Note when the connection to the server is lost, returning whether
+    /// that just happened. The error is only reported the first time.
+    pub fn update_connection(&mut self, closed: bool) -> bool {
+        if !closed || self.disconnected {
+            return false;
+        }
+        self.disconnected = true;
+        self.error_message =
+            Some("Lost the connection to the server; check Server Logs for why".to_string());
+        true
     }
 
-    pub fn update_debug_logs(&mut self, logs: Vec<LogEntry>) {
+    pub fn update_debug_logs(&mut self, mut logs: Vec<LogEntry>) {
+        if !self.redact_patterns.is_empty() {
+            for entry in &mut logs {
+                entry.message = redact(std::mem::take(&mut entry.message), &self.redact_patterns);
+            }
+        }
         self.debug_logs = logs;
     }
 
     pub fn next_item(&mut self) {
-        if self.detail_view.is_some() {
+        if let Some(detail) = &self.detail_view {
             // Scroll detail view
-            self.detail_scroll = self.detail_scroll.saturating_add(1);
+            self.detail_scroll =
+                (self.detail_scroll + 1).min(detail.line_count().saturating_sub(1));
             return;
         }
 
@@ -225,12 +1022,8 @@ impl App {
             Tab::ServerInfo => {
                 self.server_info_scroll = self.server_info_scroll.saturating_add(1);
             }
-            Tab::ServerLogs if !self.logs.is_empty() => {
-                self.log_scroll = self.log_scroll.saturating_add(1);
-            }
-            Tab::DebugLogs if !self.debug_logs.is_empty() => {
-                self.debug_log_scroll = self.debug_log_scroll.saturating_add(1);
-            }
+            Tab::ServerLogs if !self.logs.is_empty() => self.scroll_logs_down(1),
+            Tab::DebugLogs if !self.debug_logs.is_empty() => self.scroll_logs_down(1),
             _ => {}
         }
     }
@@ -267,34 +1060,90 @@ impl App {
             Tab::ServerInfo => {
                 self.server_info_scroll = self.server_info_scroll.saturating_sub(1);
             }
-            Tab::ServerLogs => {
-                self.log_scroll = self.log_scroll.saturating_sub(1);
-            }
-            Tab::DebugLogs => {
-                self.debug_log_scroll = self.debug_log_scroll.saturating_sub(1);
-            }
+            Tab::ServerLogs | Tab::DebugLogs => self.scroll_logs_up(1),
             _ => {}
         }
     }
 
     pub fn scroll_to_bottom(&mut self) {
+        self.problem_mark = None;
         match self.current_tab {
             Tab::ServerLogs if !self.logs.is_empty() => {
-                self.log_scroll = self.logs.len().saturating_sub(1);
+                self.log_scroll = self.log_bottom.get();
+                self.log_follow = true;
             }
             Tab::DebugLogs if !self.debug_logs.is_empty() => {
-                self.debug_log_scroll = self.debug_logs.len().saturating_sub(1);
+                self.debug_log_scroll = self.debug_log_bottom.get();
+                self.debug_log_follow = true;
             }
             _ => {}
         }
     }
 
+    /// Turn following new lines on or off for the current logs tab. Turning
+    /// it off leaves the view where it is.
+    pub fn toggle_log_follow(&mut self) {
+        self.problem_mark = None;
+        if let Some((scroll, follow, bottom)) = self.log_view() {
+            if *follow {
+                *scroll = bottom;
+            }
+            *follow = !*follow;
+        }
+    }
+
+    /// Scroll position, follow flag and bottom of the current logs tab.
+    fn log_view(&mut self) -> Option<(&mut usize, &mut bool, usize)> {
+        match self.current_tab {
+            Tab::ServerLogs => Some((
+                &mut self.log_scroll,
+                &mut self.log_follow,
+                self.log_bottom.get(),
+            )),
+            Tab::DebugLogs => Some((
+                &mut self.debug_log_scroll,
+                &mut self.debug_log_follow,
+                self.debug_log_bottom.get(),
+            )),
+            _ => None,
+        }
+    }
+
+    /// Scrolling up stops following new lines, from wherever the view was.
+    fn scroll_logs_up(&mut self, rows: usize) {
+        self.problem_mark = None;
+        if let Some((scroll, follow, bottom)) = self.log_view() {
+            if *follow {
+                *scroll = bottom;
+                *follow = false;
+            }
+            // Past the bottom (e.g. after a resize) the view shows the bottom
+            *scroll = (*scroll).min(bottom).saturating_sub(rows);
+        }
+    }
+
+    /// Scrolling back down to the last rows follows new lines again.
+    fn scroll_logs_down(&mut self, rows: usize) {
+        self.problem_mark = None;
+        if let Some((scroll, follow, bottom)) = self.log_view() {
+            if *follow {
+                return;
+            }
+            *scroll = scroll.saturating_add(rows);
+            if *scroll >= bottom {
+                *scroll = bottom;
+                *follow = true;
+            }
+        }
+    }
+
     pub fn page_down(&mut self) {
         const PAGE_SIZE: usize = 10;
 
-        if self.detail_view.is_some() {
+        if let Some(detail) = &self.detail_view {
             // Scroll detail view down by page
-            self.detail_scroll = self.detail_scroll.saturating_add(PAGE_SIZE);
+            self.detail_scroll =
+                (self.detail_scroll + PAGE_SIZE).min(detail.line_count().saturating_sub(1));
             return;
         }
 
@@ -302,12 +1151,8 @@ impl App {
             Tab::ServerInfo => {
                 self.server_info_scroll = self.server_info_scroll.saturating_add(PAGE_SIZE);
             }
-            Tab::ServerLogs if !self.logs.is_empty() => {
-                self.log_scroll = self.log_scroll.saturating_add(PAGE_SIZE);
-            }
-            Tab::DebugLogs if !self.debug_logs.is_empty() => {
-                self.debug_log_scroll = self.debug_log_scroll.saturating_add(PAGE_SIZE);
-            }
+            Tab::ServerLogs if !self.logs.is_empty() => self.scroll_logs_down(PAGE_SIZE),
+            Tab::DebugLogs if !self.debug_logs.is_empty() => self.scroll_logs_down(PAGE_SIZE),
             _ => {}
         }
     }
@@ -325,28 +1170,64 @@ impl App {
             Tab::ServerInfo => {
                 self.server_info_scroll = self.server_info_scroll.saturating_sub(PAGE_SIZE);
             }
-            Tab::ServerLogs => {
-                self.log_scroll = self.log_scroll.saturating_sub(PAGE_SIZE);
-            }
-            Tab::DebugLogs => {
-                self.debug_log_scroll = self.debug_log_scroll.saturating_sub(PAGE_SIZE);
-            }
+            Tab::ServerLogs | Tab::DebugLogs => self.scroll_logs_up(PAGE_SIZE),
             _ => {}
         }
     }
 
+    /// Name of the selected tool or prompt, or URI of the selected resource.
+    pub fn selected_identifier(&self) -> Option<&str> {
+        if self.detail_view.is_some() {
+            return None;
+        }
+        match self.current_tab {
+            Tab::Tools => self.tools.get(self.selected_tool).map(|t| t.name.as_str()),
+            Tab::Prompts => self
+                .prompts
+                .get(self.selected_prompt)
+                .map(|p| p.name.as_str()),
+            Tab::Resources => self
+                .resources
+                .get(self.selected_resource)
+                .map(|r| r.uri.as_str()),
+            _ => None,
+        }
+    }
+
     pub fn show_detail(&mut self) {
+        // The detail view no longer shows a read result that could be re-rendered
+        self.resource_read_source = None;
+        self.cached_request = None;
+        self.embedded_resources.clear();
+        self.prompt_json_arguments = None;
+        self.raw_item_json = false;
+        self.item_detail = true;
         match self.current_tab {
             Tab::Tools if !self.tools.is_empty() => {
                 let tool = &self.tools[self.selected_tool];
-                let detail = format!(
-                    "Tool: {}\n\nDescription: {}\n\nInput Schema:\n{}",
-                    tool.name,
+                let mut detail = format!("Tool: {}", tool.name);
+                if let Some(title) = &tool.title {
+                    detail.push_str(&format!("\nTitle: {}", title));
+                }
+                detail.push_str(&format!(
+                    "\n\nDescription: {}\n\nInput Schema:\n{}",
                     tool.description.as_deref().unwrap_or("No description"),
                     serde_json::to_string_pretty(&tool.input_schema).unwrap_or_default()
-                );
-                self.detail_view = Some(detail);
-            }
+                ));
+                if let Some(output_schema) = &tool.output_schema {
+                    detail.push_str(&format!(
+                        "\n\nOutput Schema:\n{}",
+                        serde_json::to_string_pretty(output_schema).unwrap_or_default()
+                    ));
+                }
+                if let Some(annotations) = &tool.annotations {
+                    detail.push_str(&format!(
+                        "\n\nAnnotations:\n{}",
+                        serde_json::to_string_pretty(annotations).unwrap_or_default()
+                    ));
+                }
+                self.detail_view = Some(DetailView::new(detail));
+            }
             Tab::Prompts if !self.prompts.is_empty() => {
                 let prompt = &self.prompts[self.selected_prompt];
                 let args = if let Some(arguments) = &prompt.arguments {
@@ -376,7 +1257,7 @@ impl App {
                     prompt.description.as_deref().unwrap_or("No description"),
                     args
                 );
-                self.detail_view = Some(detail);
+                self.detail_view = Some(DetailView::new(detail));
             }
             Tab::Resources if !self.resources.is_empty() => {
                 let resource = &self.resources[self.selected_resource];
@@ -387,7 +1268,7 @@ impl App {
                     resource.description.as_deref().unwrap_or("No description"),
                     resource.mime_type.as_deref().unwrap_or("Unknown")
                 );
-                self.detail_view = Some(detail);
+                self.detail_view = Some(DetailView::new(detail));
             }
             Tab::ServerInfo => {
                 if let Some(info) = &self.server_info {
@@ -402,22 +1283,175 @@ impl App {
                         if caps.resources.is_some() { "Yes" } else { "No" },
                         if caps.logging.is_some() { "Yes" } else { "No" },
                     );
+                    detail.push_str("\n  Experimental:");
+                    match &caps.experimental {
+                        Some(experimental) if !experimental.is_empty() => {
+                            for name in caps.experimental_names() {
+                                detail
+                                    .push_str(&format!("\n    - {}: {}", name, experimental[name]));
+                            }
+                        }
+                        _ => detail.push_str(" None"),
+                    }
+                    if let Some(params) = &self.client_params {
+                        detail.push_str(&format!(
+                            "\n\nClient Capabilities (sent by {} v{}):\n  Protocol Version: {}\n  Roots: {}\n  Sampling: {}",
+                            params.client_info.name,
+                            params.client_info.version,
+                            params.protocol_version,
+                            roots_summary(params.capabilities.roots.as_ref()),
+                            if params.capabilities.sampling.is_some() { "Yes" } else { "No" },
+                        ));
+                        for (name, value) in &params.capabilities.extra {
+                            detail.push_str(&format!("\n  {}: {}", name, value));
+                        }
+                        detail.push_str("\n  Configured Roots:");
+                        if self.roots.is_empty() {
+                            detail.push_str(" None");
+                        }
+                        for root in &self.roots {
+                            detail.push_str(&format!("\n    - {}", describe_root(root)));
+                        }
+                    }
                     if let Some(instructions) = &info.instructions {
                         detail.push_str("\n\nInstructions:\n");
                         for line in instructions.lines() {
                             detail.push_str(&format!("  {}\n", line));
                         }
                     }
-                    self.detail_view = Some(detail);
+                    self.detail_view = Some(DetailView::new(detail));
                 }
             }
             _ => {}
         }
     }
 
+    /// Switch binary resource contents between a size summary and a hex
+    /// dump, re-rendering the resource read result being shown.
+    pub fn toggle_hex_dump(&mut self) {
+        if self.current_tab != Tab::Resources || self.detail_view.is_none() {
+            return;
+        }
+        let (Some(contents), Some((resource_name, uri))) =
+            (&self.resource_read_result, &self.resource_read_source)
+        else {
+            return;
+        };
+
+        self.settings.hex_dump = !self.settings.hex_dump;
+        self.detail_view = Some(DetailView::new(redact(
+            format_resource_read_result(resource_name, uri, contents, self.settings.hex_dump),
+            &self.redact_patterns,
+        )));
+    }
+
+    /// Whether the detail view describes the selected item, so `J` can
+    /// switch it to the raw JSON.
+    pub fn item_detail_shown(&self) -> bool {
+        self.item_detail && self.detail_view.is_some()
+    }
+
+    /// Switch the detail view of the selected item between its summary and
+    /// the JSON definition the server sent.
+    pub fn toggle_item_json(&mut self) {
+        if !self.item_detail_shown() {
+            return;
+        }
+        if self.raw_item_json {
+            self.show_detail();
+            return;
+        }
+
+        let json = match self.current_tab {
+            Tab::Tools => self
+                .tools
+                .get(self.selected_tool)
+                .map(serde_json::to_string_pretty),
+            Tab::Prompts => self
+                .prompts
+                .get(self.selected_prompt)
+                .map(serde_json::to_string_pretty),
+            Tab::Resources => self
+                .resources
+                .get(self.selected_resource)
+                .map(serde_json::to_string_pretty),
+            Tab::ServerInfo => self.server_info.as_ref().map(serde_json::to_string_pretty),
+            _ => None,
+        };
+        if let Some(Ok(json)) = json {
+            self.detail_view = Some(DetailView::new(json));
+            self.detail_scroll = 0;
+            self.raw_item_json = true;
+        }
+    }
+
+    /// Scroll the detail view to the next (or previous) section title.
+    pub fn jump_to_section(&mut self, forward: bool) {
+        let Some(detail) = &self.detail_view else {
+            return;
+        };
+        let rows = detail.section_rows();
+        let found = if forward {
+            rows.into_iter().find(|&row| row > self.detail_scroll)
+        } else {
+            rows.into_iter().rev().find(|&row| row < self.detail_scroll)
+        };
+        if let Some(row) = found {
+            self.detail_scroll = row;
+        }
+    }
+
+    /// Collapse or expand the section at the top of the detail view.
+    pub fn toggle_section(&mut self) {
+        if let Some(detail) = &mut self.detail_view {
+            if let Some(row) = detail.toggle_section(self.detail_scroll) {
+                self.detail_scroll = row;
+            }
+        }
+    }
+
+    /// Collapse every section of the detail view for an overview, or expand
+    /// them all again.
+    pub fn toggle_all_sections(&mut self) {
+        if let Some(detail) = &mut self.detail_view {
+            if detail.has_sections() {
+                detail.toggle_all_sections();
+                self.detail_scroll = 0;
+            }
+        }
+    }
+
+    /// Replace a truncated detail view with its full content.
+    pub fn show_full_detail(&mut self) {
+        if let Some(detail) = &self.detail_view {
+            if detail.is_truncated() {
+                self.detail_view = Some(DetailView::full(detail.text()));
+            }
+        }
+    }
+
+    /// Write the detail view's full content to a file in the current
+    /// directory, returning the file name.
+    pub fn save_detail(&self) -> Result<String> {
+        let detail = self
+            .detail_view
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No detail view open"))?;
+
+        let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
+        let filename = format!("mcpeek_result_{}.txt", timestamp);
+        std::fs::write(&filename, detail.text())?;
+
+        Ok(filename)
+    }
+
     pub fn close_detail(&mut self) {
         self.detail_view = None;
+        self.item_detail = false;
         self.detail_scroll = 0;
+        self.cached_request = None;
+        self.embedded_resources.clear();
+        self.prompt_json_arguments = None;
     }
 
     pub fn quit(&mut self) {
@@ -436,6 +1470,125 @@ impl App {
         self.tool_input_scroll = 0;
         self.tool_call_input_mode = true;
         self.tool_call_result = None;
+        self.raw_json_mode = false;
+        self.raw_json_input.clear();
+        self.input_cursors.clear();
+        self.clear_tool_call_error();
+    }
+
+    /// Select the tool named on the command line and open its call form with
+    /// the given arguments filled in, without running it.
+    fn open_initial_tool_call(&mut self, call: InitialToolCall) {
+        let Some(index) = self.tools.iter().position(|t| t.name == call.tool_name) else {
+            let hint = if self.unselected_tools > 0 {
+                " (is it left out by --select?)"
+            } else {
+                ""
+            };
+            self.error_message = Some(format!("Tool '{}' not found{}", call.tool_name, hint));
+            return;
+        };
+        if !self.tabs.contains(&Tab::Tools) {
+            self.error_message = Some("--tool needs the Tools tab, which --tabs hides".to_string());
+            return;
+        }
+        // With unknown arguments, leave the form for the user to fix rather
+        // than run without them
+        if self.open_prefilled_tool_call(index, call.arguments) && call.execute {
+            self.run_initial_tool_call = true;
+        }
+    }
+
+    /// Open the call form of the `index`th tool in the Tools tab with the
+    /// given input text filled in. Returns false, with the form showing an
+    /// error, if the tool has no parameter for some of them.
+    fn open_prefilled_tool_call(&mut self, index: usize, arguments: Vec<(String, String)>) -> bool {
+        self.current_tab = Tab::Tools;
+        self.selected_tool = index;
+        self.start_tool_call();
+
+        let mut unknown = Vec::new();
+        for (name, value) in arguments {
+            if self.input_fields.iter().any(|f| f.name == name) {
+                self.tool_call_inputs.insert(name, value);
+            } else {
+                unknown.push(name);
+            }
+        }
+        if unknown.is_empty() {
+            return true;
+        }
+        self.tool_call_error = Some(format!(
+            "'{}' has no parameter named {}",
+            self.tools[index].name,
+            unknown.join(", ")
+        ));
+        false
+    }
+
+    /// Open a tool call form filled in from the JSON object found in the
+    /// prompt result being shown.
+    pub fn use_prompt_json_as_tool_arguments(&mut self) {
+        let Some(object) = self.prompt_json_arguments.clone() else {
+            return;
+        };
+        if !self.tabs.contains(&Tab::Tools) {
+            self.error_message = Some("The Tools tab is hidden".to_string());
+            return;
+        }
+        let Some((index, arguments)) =
+            match_tool_arguments(&self.tools, self.selected_tool, object)
+        else {
+            self.error_message =
+                Some("No tool takes the arguments in the prompt result".to_string());
+            return;
+        };
+
+        info!(
+            action = "prompt_to_tool",
+            "Filling in {} from the prompt result", self.tools[index].name
+        );
+        self.close_detail();
+        let arguments = arguments
+            .into_iter()
+            .map(|(name, value)| {
+                let text = match value {
+                    Value::String(text) => text,
+                    other => other.to_string(),
+                };
+                (name, text)
+            })
+            .collect();
+        self.open_prefilled_tool_call(index, arguments);
+    }
+
+    /// Execute the `--once` tool call after its form has been opened,
+    /// returning whether it was started.
+    pub fn run_initial_tool_call(&mut self, client: &Arc<McpClient>) -> bool {
+        if !std::mem::take(&mut self.run_initial_tool_call) {
+            return false;
+        }
+        self.execute_tool_call(client);
+        true
+    }
+
+    /// Switch the tool call form between per-field input and a single raw
+    /// JSON editor for the whole arguments object.
+    pub fn toggle_raw_json_mode(&mut self) {
+        if !self.tool_call_input_mode {
+            return;
+        }
+
+        self.raw_json_mode = !self.raw_json_mode;
+        self.tool_input_scroll = 0;
+
+        if self.raw_json_mode && self.raw_json_input.trim().is_empty() {
+            // Seed the editor with whatever the form already holds
+            let arguments = self.build_tool_arguments().unwrap_or_default();
+            self.raw_json_input =
+                serde_json::to_string_pretty(&arguments).unwrap_or_else(|_| "{}".to_string());
+            self.raw_json_cursor = usize::MAX;
+        }
     }
 
     pub fn next_input_field(&mut self) {
@@ -455,49 +1608,228 @@ impl App {
     }
 
     pub fn update_current_input(&mut self, c: char) {
-        if self.input_fields.is_empty() {
-            return;
+        self.insert_input(c.encode_utf8(&mut [0; 4]));
+    }
+
+    /// Insert a whole pasted string into the focused input at once.
+    pub fn paste_input(&mut self, text: &str) {
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+
+        if self.text_prompt.is_some() {
+            // Text prompts are single-line
+            self.insert_input(&text.trim_end_matches('\n').replace('\n', " "));
+        } else {
+            self.insert_input(&text);
         }
-        let field_name = &self.input_fields[self.input_field_index].name;
+    }
 
-        if self.tool_call_input_mode {
-            self.tool_call_inputs
-                .entry(field_name.clone())
-                .or_default()
-                .push(c);
-        } else if self.prompt_input_mode {
-            self.prompt_inputs
-                .entry(field_name.clone())
-                .or_default()
-                .push(c);
+    fn insert_input(&mut self, text: &str) {
+        if let Some((value, cursor)) = self.focused_input() {
+            value.insert_str(byte_offset(value, *cursor), text);
+            *cursor += text.chars().count();
         }
     }
 
+    /// Delete the character before the cursor.
     pub fn delete_current_input(&mut self) {
-        if self.input_fields.is_empty() {
-            return;
+        if let Some((value, cursor)) = self.focused_input() {
+            if *cursor > 0 {
+                *cursor -= 1;
+                value.remove(byte_offset(value, *cursor));
+            }
+        }
+    }
+
+    /// Delete the character under the cursor.
+    pub fn delete_input_forward(&mut self) {
+        if let Some((value, cursor)) = self.focused_input() {
+            if *cursor < value.chars().count() {
+                value.remove(byte_offset(value, *cursor));
+            }
         }
-        let field_name = &self.input_fields[self.input_field_index].name;
+    }
 
-        if self.tool_call_input_mode {
-            if let Some(value) = self.tool_call_inputs.get_mut(field_name) {
-                value.pop();
+    /// Delete the word before the cursor, readline-style (Ctrl+W).
+    pub fn delete_word_before_cursor(&mut self) {
+        if let Some((value, cursor)) = self.focused_input() {
+            let chars: Vec<char> = value.chars().collect();
+            let mut start = *cursor;
+            while start > 0 && chars[start - 1].is_whitespace() {
+                start -= 1;
             }
-        } else if self.prompt_input_mode {
-            if let Some(value) = self.prompt_inputs.get_mut(field_name) {
-                value.pop();
+            while start > 0 && !chars[start - 1].is_whitespace() {
+                start -= 1;
             }
+
+            value.replace_range(byte_offset(value, start)..byte_offset(value, *cursor), "");
+            *cursor = start;
+        }
+    }
+
+    /// Clear the whole focused input (Ctrl+U).
+    pub fn clear_current_input(&mut self) {
+        if let Some((value, cursor)) = self.focused_input() {
+            value.clear();
+            *cursor = 0;
+        }
+    }
+
+    pub fn move_cursor_left(&mut self) {
+        if let Some((_, cursor)) = self.focused_input() {
+            *cursor = cursor.saturating_sub(1);
+        }
+    }
+
+    pub fn move_cursor_right(&mut self) {
+        if let Some((value, cursor)) = self.focused_input() {
+            *cursor = (*cursor + 1).min(value.chars().count());
+        }
+    }
+
+    pub fn move_cursor_home(&mut self) {
+        if let Some((_, cursor)) = self.focused_input() {
+            *cursor = 0;
+        }
+    }
+
+    pub fn move_cursor_end(&mut self) {
+        if let Some((value, cursor)) = self.focused_input() {
+            *cursor = value.chars().count();
         }
     }
 
-    pub async fn execute_tool_call(&mut self, client: &McpClient) {
-        if self.tools.is_empty() {
+    /// Cursor position of a form field, defaulting to the end of its value.
+    pub fn field_cursor(&self, field_name: &str, value: &str) -> usize {
+        self.input_cursors
+            .get(field_name)
+            .copied()
+            .unwrap_or(usize::MAX)
+            .min(value.chars().count())
+    }
+
+    /// The focused text input together with its cursor, clamped to the
+    /// current value. Cursors are character indices, not byte offsets.
+    fn focused_input(&mut self) -> Option<(&mut String, &mut usize)> {
+        let (value, cursor) = if let Some(prompt) = &mut self.text_prompt {
+            (&mut prompt.value, &mut prompt.cursor)
+        } else if self.tool_call_input_mode && self.raw_json_mode {
+            (&mut self.raw_json_input, &mut self.raw_json_cursor)
+        } else if self.tool_call_input_mode || self.prompt_input_mode {
+            let name = self.input_fields.get(self.input_field_index)?.name.clone();
+            let inputs = if self.tool_call_input_mode {
+                &mut self.tool_call_inputs
+            } else {
+                &mut self.prompt_inputs
+            };
+            let value = inputs.entry(name.clone()).or_default();
+            let cursor = self.input_cursors.entry(name).or_insert(usize::MAX);
+            (value, cursor)
+        } else {
+            return None;
+        };
+
+        *cursor = (*cursor).min(value.chars().count());
+        Some((value, cursor))
+    }
+
+    pub fn execute_tool_call(&mut self, client: &Arc<McpClient>) {
+        if self.tools.is_empty() || self.reject_if_busy() {
+            return;
+        }
+
+        let arguments = match self.collect_tool_arguments() {
+            Ok(arguments) => arguments,
+            Err(e) => {
+                self.set_tool_call_error(e);
+                return;
+            }
+        };
+
+        // Call the tool in the background
+        let tool_name = self.tools[self.selected_tool].name.clone();
+        let arguments = if arguments.is_empty() {
+            None
+        } else {
+            Some(arguments)
+        };
+
+        if let Some(reason) = self.confirmation_reason() {
+            self.tool_confirmation = Some(ToolConfirmation {
+                tool_name,
+                reason,
+                arguments,
+            });
             return;
         }
 
+        self.spawn_tool_call(client, tool_name, arguments);
+    }
+
+    /// Why the selected tool needs confirming before it runs, if it does.
+    fn confirmation_reason(&self) -> Option<String> {
+        if !self.confirm_destructive {
+            return None;
+        }
+
         let tool = &self.tools[self.selected_tool];
+        if tool.hint("destructiveHint") == Some(true) {
+            return Some("is marked as destructive".to_string());
+        }
+
+        let name = tool.name.to_lowercase();
+        self.confirm_patterns
+            .iter()
+            .find(|pattern| name.contains(&pattern.to_lowercase()))
+            .map(|pattern| format!("matches the confirmation pattern '{}'", pattern))
+    }
+
+    /// Run the tool call waiting on confirmation.
+    pub fn confirm_tool_call(&mut self, client: &Arc<McpClient>) {
+        if let Some(confirmation) = self.tool_confirmation.take() {
+            self.spawn_tool_call(client, confirmation.tool_name, confirmation.arguments);
+        }
+    }
+
+    /// Drop the tool call waiting on confirmation, returning to the form.
+    pub fn cancel_tool_confirmation(&mut self) {
+        self.tool_confirmation = None;
+    }
+
+    fn spawn_tool_call(
+        &mut self,
+        client: &Arc<McpClient>,
+        tool_name: String,
+        arguments: Option<HashMap<String, Value>>,
+    ) {
+        if self.reject_if_busy() {
+            return;
+        }
+
+        self.tool_call_input_mode = false;
+        self.clear_tool_call_error();
+
+        self.tool_last_used
+            .insert(tool_name.clone(), Instant::now());
+        if self.settings.tool_sort == Some(ToolSort::Recent) {
+            self.resort_tools();
+        }
+
+        self.fetch(
+            client,
+            CachedRequest::ToolCall {
+                tool_name,
+                arguments,
+            },
+        );
+    }
+
+    /// Gather the arguments for the call from either the raw JSON editor or
+    /// the per-field form, validating required fields.
+    fn collect_tool_arguments(&self) -> std::result::Result<HashMap<String, Value>, String> {
+        if self.raw_json_mode {
+            return parse_raw_json_arguments(&self.raw_json_input);
+        }
 
-        // Validate required fields
         for field in &self.input_fields {
             if field.required {
                 let value = self
@@ -506,98 +1838,184 @@ impl App {
                     .map(|s| s.trim())
                     .unwrap_or("");
                 if value.is_empty() {
-                    self.error_message = Some(format!("Required field '{}' is empty", field.name));
-                    return;
+                    return Err(format!("Required field '{}' is empty", field.name));
                 }
             }
         }
 
-        // Convert inputs to JSON values
+        self.build_tool_arguments()
+    }
+
+    /// Show an error inline in the tool call form, moving focus to the field
+    /// it mentions if there is one.
+    fn set_tool_call_error(&mut self, error: String) {
+        self.tool_call_error_field = None;
+        if !self.raw_json_mode {
+            if let Some(index) = error_field_index(&self.input_fields, &error) {
+                self.input_field_index = index;
+                self.tool_call_error_field = Some(self.input_fields[index].name.clone());
+            }
+        }
+        self.tool_call_error = Some(error);
+    }
+
+    fn clear_tool_call_error(&mut self) {
+        self.tool_call_error = None;
+        self.tool_call_error_field = None;
+    }
+
+    /// Reopen the form for a call that failed, keeping what was entered so
+    /// it can be corrected and re-submitted. Returns false if the form can't
+    /// be restored (another input is open or the tool has gone away).
+    fn reopen_failed_tool_call(&mut self, tool_name: &str, error: String) -> bool {
+        if self.prompt_input_mode || self.text_prompt.is_some() {
+            return false;
+        }
+        let Some(index) = self.tools.iter().position(|t| t.name == tool_name) else {
+            return false;
+        };
+
+        self.selected_tool = index;
+        self.input_fields = parse_input_schema(&self.tools[index].input_schema);
+        self.input_field_index = self
+            .input_field_index
+            .min(self.input_fields.len().saturating_sub(1));
+        self.tool_call_input_mode = true;
+        self.set_tool_call_error(error);
+        true
+    }
+
+    /// Convert the per-field form inputs into JSON arguments, coercing each
+    /// value according to its schema type.
+    fn build_tool_arguments(&self) -> std::result::Result<HashMap<String, Value>, String> {
         let mut arguments = HashMap::new();
         for field in &self.input_fields {
             if let Some(value_str) = self.tool_call_inputs.get(&field.name) {
                 let value_str = value_str.trim();
                 if !value_str.is_empty() {
-                    let json_value = match field.field_type.as_str() {
-                        "number" | "integer" => {
-                            if let Ok(num) = value_str.parse::<i64>() {
-                                Value::Number(num.into())
-                            } else if let Ok(num) = value_str.parse::<f64>() {
-                                Value::Number(
-                                    serde_json::Number::from_f64(num).unwrap_or_else(|| 0.into()),
-                                )
-                            } else {
-                                self.error_message =
-                                    Some(format!("'{}' must be a number", field.name));
-                                return;
-                            }
-                        }
-                        "boolean" => match value_str.to_lowercase().as_str() {
-                            "true" | "yes" | "1" => Value::Bool(true),
-                            "false" | "no" | "0" => Value::Bool(false),
-                            _ => {
-                                self.error_message =
-                                    Some(format!("'{}' must be true or false", field.name));
-                                return;
-                            }
-                        },
-                        "array" | "object" => {
-                            // Try to parse as JSON
-                            match serde_json::from_str(value_str) {
-                                Ok(v) => v,
-                                Err(_) => {
-                                    self.error_message =
-                                        Some(format!("'{}' must be valid JSON", field.name));
-                                    return;
-                                }
-                            }
+                    let json_value = match (field.field_type.as_str(), &field.item_type) {
+                        // A list of primitives may be typed as plain comma-separated values
+                        ("array", Some(item_type)) if !value_str.starts_with('[') => {
+                            let items = value_str
+                                .split(',')
+                                .map(str::trim)
+                                .filter(|item| !item.is_empty())
+                                .map(|item| coerce_input_value(&field.name, item_type, item))
+                                .collect::<std::result::Result<Vec<_>, _>>()?;
+                            Value::Array(items)
                         }
-                        _ => Value::String(value_str.to_string()),
+                        (field_type, _) => coerce_input_value(&field.name, field_type, value_str)?,
                     };
                     arguments.insert(field.name.clone(), json_value);
                 }
             }
         }
-
-        // Call the tool
-        let tool_name = tool.name.clone();
-        match client
-            .call_tool(
-                &tool_name,
-                if arguments.is_empty() {
-                    None
-                } else {
-                    Some(arguments)
-                },
-            )
-            .await
-        {
-            Ok(result) => {
-                self.tool_call_result = Some(result.clone());
-                self.tool_call_input_mode = false;
-
-                // Show result in detail view
-                let detail = format_tool_result(&tool_name, &result);
-                self.detail_view = Some(detail);
-            }
-            Err(e) => {
-                self.error_message = Some(format!("Tool call failed: {}", e));
-            }
-        }
+        Ok(arguments)
     }
 
     pub fn cancel_tool_call(&mut self) {
         self.tool_call_input_mode = false;
+        self.raw_json_mode = false;
+        self.raw_json_input.clear();
+        self.input_cursors.clear();
         self.tool_call_inputs.clear();
         self.input_fields.clear();
         self.input_field_index = 0;
         self.tool_input_scroll = 0;
+        self.clear_tool_call_error();
     }
 
     pub fn scroll_tool_input_up(&mut self) {
         self.tool_input_scroll = self.tool_input_scroll.saturating_sub(1);
     }
 
+    /// Re-clamp scroll positions after the terminal is resized, so views
+    /// and overlays don't end up scrolled past their content. Log scroll
+    /// positions are in wrapped rows, so the renderer clamps those.
+    pub fn handle_resize(&mut self, width: u16, height: u16) {
+        let (width, height) = (width as usize, height as usize);
+
+        // The detail view sits between the tab bar and help bar, inside borders
+        if let Some(detail) = &self.detail_view {
+            let rows = height.saturating_sub(8);
+            let lines = detail.line_count();
+            self.detail_scroll = self.detail_scroll.min(lines.saturating_sub(rows));
+        }
+
+        if self.tool_call_input_mode || self.prompt_input_mode {
+            let popup = self.input_popup(width as u16, height as u16);
+            let rows = popup.height.saturating_sub(4) as usize;
+            let lines = self.input_form_height(popup.width.saturating_sub(4) as usize);
+            self.tool_input_scroll = self.tool_input_scroll.min(lines.saturating_sub(rows));
+        }
+
+        // Lists scroll themselves to the selection; just don't start past it
+        for (offset, selected) in [
+            (&self.tool_list_offset, self.selected_tool),
+            (&self.prompt_list_offset, self.selected_prompt),
+            (&self.resource_list_offset, self.selected_resource),
+        ] {
+            offset.set(offset.get().min(selected));
+        }
+    }
+
+    /// Size of the open tool or prompt input form popup in a terminal of
+    /// `width` x `height`. Only the tool form shows an inline error or the
+    /// raw JSON editor.
+    pub fn input_popup(&self, width: u16, height: u16) -> InputPopup {
+        let popup_width = width.saturating_sub(10).min(80);
+        let error_rows = self.input_error_rows(popup_width.saturating_sub(4) as usize) as u16;
+        let popup_height = if self.tool_call_input_mode && self.raw_json_mode {
+            height.saturating_sub(4).min(24 + error_rows)
+        } else {
+            (self.input_fields.len() as u16 * 3 + 8 + error_rows).min(height.saturating_sub(4))
+        };
+        InputPopup {
+            width: popup_width,
+            height: popup_height,
+            error_rows,
+        }
+    }
+
+    /// Rows an inline tool form error takes inside a popup `width` wide:
+    /// up to four wrapped lines, plus a blank separator line.
+    fn input_error_rows(&self, width: usize) -> usize {
+        match &self.tool_call_error {
+            Some(error) if self.tool_call_input_mode => {
+                error.chars().count().div_ceil(width.max(1)).clamp(1, 4) + 1
+            }
+            _ => 0,
+        }
+    }
+
+    /// Number of rows the open input form's content takes at `width`.
+    fn input_form_height(&self, width: usize) -> usize {
+        let error = self.input_error_rows(width);
+        if self.tool_call_input_mode && self.raw_json_mode {
+            return error + 2 + wrapped_height(&self.raw_json_input, width);
+        }
+
+        let inputs = if self.tool_call_input_mode {
+            &self.tool_call_inputs
+        } else {
+            &self.prompt_inputs
+        };
+        let fields: usize = self
+            .input_fields
+            .iter()
+            .map(|field| {
+                let description = field
+                    .description
+                    .as_deref()
+                    .map_or(0, |d| wrapped_height(d, width.saturating_sub(2)));
+                let value = inputs.get(&field.name).map(String::as_str).unwrap_or("");
+                // Label, description, value, then a blank separator line
+                1 + description + wrapped_height(value, width.saturating_sub(2)) + 1
+            })
+            .sum();
+        error + fields.saturating_sub(1)
+    }
+
     pub fn scroll_tool_input_down(&mut self) {
         // We'll add bounds checking in the UI render function
         self.tool_input_scroll = self.tool_input_scroll.saturating_add(1);
@@ -617,6 +2035,7 @@ impl App {
                 .map(|arg| InputField {
                     name: arg.name.clone(),
                     field_type: "string".to_string(),
+                    item_type: None,
                     required: arg.required.unwrap_or(false),
                     description: arg.description.clone(),
                 })
@@ -626,14 +2045,15 @@ impl App {
         };
 
         self.prompt_inputs.clear();
+        self.input_cursors.clear();
         self.input_field_index = 0;
         self.tool_input_scroll = 0;
         self.prompt_input_mode = true;
         self.prompt_result = None;
     }
 
-    pub async fn execute_prompt_get(&mut self, client: &McpClient) {
-        if self.prompts.is_empty() {
+    pub fn execute_prompt_get(&mut self, client: &Arc<McpClient>) {
+        if self.prompts.is_empty() || self.reject_if_busy() {
             return;
         }
 
@@ -665,87 +2085,682 @@ impl App {
             }
         }
 
-        // Get the prompt
+        // Get the prompt in the background
         let prompt_name = prompt.name.clone();
-        match client
-            .get_prompt(
-                &prompt_name,
-                if arguments.is_empty() {
-                    None
-                } else {
-                    Some(arguments)
-                },
-            )
-            .await
-        {
-            Ok(result) => {
-                self.prompt_result = Some(result.clone());
-                self.prompt_input_mode = false;
+        let arguments = if arguments.is_empty() {
+            None
+        } else {
+            Some(arguments)
+        };
+        self.prompt_input_mode = false;
+        info!(action = "get_prompt", "Getting prompt '{}'", prompt_name);
 
-                // Show result in detail view
-                let detail = format_prompt_result(&prompt_name, &result);
-                self.detail_view = Some(detail);
-            }
-            Err(e) => {
-                self.error_message = Some(format!("Prompt get failed: {}", e));
+        let client = client.clone();
+        let description = format!("Getting prompt '{}'", prompt_name);
+        self.spawn_task(description, async move {
+            let result = client.get_prompt(&prompt_name, arguments).await;
+            TaskOutcome::PromptGet {
+                prompt_name,
+                result,
             }
-        }
+        });
     }
 
     pub fn cancel_prompt_input(&mut self) {
         self.prompt_input_mode = false;
         self.prompt_inputs.clear();
+        self.input_cursors.clear();
         self.input_fields.clear();
         self.input_field_index = 0;
         self.tool_input_scroll = 0;
     }
 
-    pub async fn read_resource(&mut self, client: &McpClient) {
-        if self.resources.is_empty() {
+    pub fn read_resource(&mut self, client: &Arc<McpClient>) {
+        if self.resources.is_empty() || self.reject_if_busy() {
             return;
         }
 
         let resource = &self.resources[self.selected_resource];
         let uri = resource.uri.clone();
         let resource_name = resource.name.clone();
+        self.spawn_resource_read(client, resource_name, uri);
+    }
+
+    /// Ask for a URI to read, for resources that aren't in the list (e.g.
+    /// templated or unlisted ones).
+    pub fn start_resource_uri_prompt(&mut self) {
+        if self.current_tab != Tab::Resources {
+            return;
+        }
+
+        let value = self
+            .resources
+            .get(self.selected_resource)
+            .map(|r| r.uri.clone())
+            .unwrap_or_default();
+        self.text_prompt = Some(TextPrompt {
+            kind: TextPromptKind::ResourceUri,
+            title: "Read Resource by URI".to_string(),
+            value,
+            cursor: usize::MAX,
+        });
+    }
 
-        match client.read_resource(&uri).await {
-            Ok(contents) => {
-                self.resource_read_result = Some(contents.clone());
+    fn spawn_resource_read(&mut self, client: &Arc<McpClient>, resource_name: String, uri: String) {
+        self.fetch(client, CachedRequest::ResourceRead { resource_name, uri });
+    }
 
-                // Show result in detail view
-                let detail = format_resource_read_result(&resource_name, &uri, &contents);
-                self.detail_view = Some(detail);
-                self.error_message = None; // Clear any previous errors
+    /// Run a tool call or resource read, serving it from the cache when
+    /// caching is on and the request is read-only.
+    fn fetch(&mut self, client: &Arc<McpClient>, request: CachedRequest) {
+        match &request {
+            CachedRequest::ToolCall { tool_name, .. } => {
+                info!(action = "call_tool", "Calling tool '{}'", tool_name)
             }
-            Err(e) => {
-                let error_msg = format!("Failed to read resource '{}': {:#}", resource_name, e);
-                self.error_message = Some(error_msg);
+            CachedRequest::ResourceRead { uri, .. } => {
+                info!(action = "read_resource", "Reading resource {}", uri)
             }
         }
+        let cacheable = self.is_cacheable(&request);
+        if cacheable && self.serve_from_cache(&request) {
+            return;
+        }
+        self.spawn_request(client, request, cacheable);
     }
 
-    pub fn export_logs(&self) -> Result<String> {
-        #[derive(Serialize)]
-        struct LogExport {
-            metadata: ExportMetadata,
-            server_logs: Vec<String>,
-            debug_logs: Vec<LogEntry>,
+    /// Only resource reads and tools annotated read-only are cached, as other
+    /// tool calls may have side effects.
+    fn is_cacheable(&self, request: &CachedRequest) -> bool {
+        if self.result_cache.is_none() {
+            return false;
         }
-
-        #[derive(Serialize)]
-        struct ExportMetadata {
-            export_timestamp: String,
-            application_version: String,
-            server_log_count: usize,
-            debug_log_count: usize,
+        match request {
+            CachedRequest::ToolCall { tool_name, .. } => self
+                .tools
+                .iter()
+                .find(|t| &t.name == tool_name)
+                .and_then(|t| t.hint("readOnlyHint"))
+                .unwrap_or(false),
+            CachedRequest::ResourceRead { .. } => true,
         }
+    }
 
-        let export = LogExport {
-            metadata: ExportMetadata {
-                export_timestamp: chrono::Utc::now()
-                    .to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
-                application_version: env!("CARGO_PKG_VERSION").to_string(),
+    fn serve_from_cache(&mut self, request: &CachedRequest) -> bool {
+        let Some(cached) = self
+            .result_cache
+            .as_mut()
+            .and_then(|cache| cache.get(&request.key()))
+        else {
+            return false;
+        };
+
+        let outcome = match (request.clone(), cached) {
+            (CachedRequest::ToolCall { tool_name, .. }, CachedResult::ToolCall(result)) => {
+                TaskOutcome::ToolCall {
+                    tool_name,
+                    result: Ok(result),
+                }
+            }
+            (
+                CachedRequest::ResourceRead { resource_name, uri },
+                CachedResult::ResourceRead(contents),
+            ) => TaskOutcome::ResourceRead {
+                resource_name,
+                uri,
+                result: Ok(contents),
+            },
+            _ => return false,
+        };
+        self.pending_cache_request = None;
+        self.apply_task_outcome(outcome);
+        self.cached_request = Some(request.clone());
+        true
+    }
+
+    /// Re-run the request behind the cached result being shown, replacing
+    /// the cached copy.
+    pub fn refresh_cached(&mut self, client: &Arc<McpClient>) {
+        if self.cached_request.is_none() || self.reject_if_busy() {
+            return;
+        }
+        if let Some(request) = self.cached_request.take() {
+            info!(
+                action = "refresh_cached",
+                "Refreshing cached result: {}",
+                request.key()
+            );
+            self.spawn_request(client, request, true);
+        }
+    }
+
+    fn spawn_request(&mut self, client: &Arc<McpClient>, request: CachedRequest, cache: bool) {
+        self.pending_cache_request = cache.then(|| request.clone());
+
+        let client = client.clone();
+        match request {
+            CachedRequest::ToolCall {
+                tool_name,
+                arguments,
+            } => {
+                let description = format!("Calling tool '{}'", tool_name);
+                self.spawn_task(description, async move {
+                    let result = client.call_tool(&tool_name, arguments).await;
+                    TaskOutcome::ToolCall { tool_name, result }
+                });
+            }
+            CachedRequest::ResourceRead { resource_name, uri } => {
+                let description = format!("Reading resource '{}'", resource_name);
+                self.spawn_task(description, async move {
+                    let result = client.read_resource(&uri).await;
+                    TaskOutcome::ResourceRead {
+                        resource_name,
+                        uri,
+                        result,
+                    }
+                });
+            }
+        }
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.pending_task.is_some()
+    }
+
+    fn reject_if_busy(&mut self) -> bool {
+        if let Some(task) = &self.pending_task {
+            self.error_message = Some(format!("{} is still in progress", task.description));
+            return true;
+        }
+        false
+    }
+
+    fn spawn<F>(&mut self, future: F) -> (u64, JoinHandle<()>)
+    where
+        F: Future<Output = TaskOutcome> + Send + 'static,
+    {
+        self.next_task_id += 1;
+        let id = self.next_task_id;
+        let tx = self.task_tx.clone();
+
+        let handle = tokio::spawn(async move {
+            let outcome = future.await;
+            let _ = tx.send(TaskMessage { id, outcome });
+        });
+
+        (id, handle)
+    }
+
+    fn spawn_load<F>(&mut self, future: F)
+    where
+        F: Future<Output = TaskOutcome> + Send + 'static,
+    {
+        let (id, handle) = self.spawn(future);
+        self.loading = true;
+        self.pending_load = Some(PendingLoad { id, handle });
+    }
+
+    fn spawn_task<F>(&mut self, description: String, future: F)
+    where
+        F: Future<Output = TaskOutcome> + Send + 'static,
+    {
+        let (id, handle) = self.spawn(future);
+        self.error_message = None;
+        self.pending_task = Some(PendingTask {
+            id,
+            description,
+            started: Instant::now(),
+            handle,
+        });
+    }
+
+    /// Abort the in-flight request, if any. A late response is discarded.
+    pub fn cancel_task(&mut self) {
+        if let Some(task) = self.pending_task.take() {
+            task.handle.abort();
+            info!(action = "cancel", "{} cancelled", task.description);
+            self.pending_cache_request = None;
+            self.error_message = Some(format!("{} cancelled", task.description));
+        }
+    }
+
+    /// Apply the results of finished background requests, returning whether
+    /// any were applied.
+    pub fn poll_tasks(&mut self) -> bool {
+        let mut changed = false;
+        while let Ok(message) = self.task_rx.try_recv() {
+            if self
+                .pending_task
+                .as_ref()
+                .is_some_and(|t| t.id == message.id)
+            {
+                self.pending_task = None;
+            } else if self
+                .pending_load
+                .as_ref()
+                .is_some_and(|l| l.id == message.id)
+            {
+                self.pending_load = None;
+                self.loading = self.pending_preload.is_some();
+            } else if self
+                .pending_preload
+                .as_ref()
+                .is_some_and(|l| l.id == message.id)
+            {
+                self.pending_preload = None;
+                self.loading = self.pending_load.is_some();
+            } else {
+                // Stale result from a cancelled request
+                continue;
+            }
+            self.apply_task_outcome(message.outcome);
+            changed = true;
+        }
+        changed
+    }
+
+    fn apply_task_outcome(&mut self, outcome: TaskOutcome) {
+        match outcome {
+            TaskOutcome::ListsLoaded(lists) => {
+                if let Some(result) = lists.tools {
+                    self.apply_task_outcome(TaskOutcome::ToolsLoaded(result));
+                }
+                if let Some(result) = lists.prompts {
+                    self.apply_task_outcome(TaskOutcome::PromptsLoaded(result));
+                }
+                if let Some(result) = lists.resources {
+                    self.apply_task_outcome(TaskOutcome::ResourcesLoaded(result));
+                }
+            }
+            TaskOutcome::ToolsLoaded(result) => match result {
+                Ok(mut tools) => {
+                    warn_duplicates("tool", tools.iter().map(|t| t.name.as_str()));
+                    if !self.tool_select.is_empty() {
+                        let listed = tools.len();
+                        tools.retain(|t| self.tool_select.iter().any(|re| re.is_match(&t.name)));
+                        self.unselected_tools = listed - tools.len();
+                    }
+                    self.last_loaded.insert(Tab::Tools, Instant::now());
+                    let previous = self.tools.get(self.selected_tool).map(|t| t.name.clone());
+                    self.listed_tool_names = tools.iter().map(|t| t.name.clone()).collect();
+                    self.required_params = tools
+                        .iter()
+                        .map(|t| (t.name.clone(), required_param_count(t)))
+                        .collect();
+                    self.tools = tools;
+                    self.sort_tools();
+                    self.selected_tool =
+                        reselect(&self.tools, previous.as_deref(), self.selected_tool, |t| {
+                            &t.name
+                        });
+                    if let Some(call) = self.initial_tool_call.take() {
+                        self.open_initial_tool_call(call);
+                    }
+                }
+                Err(e) => {
+                    self.initial_tool_call = None;
+                    self.error_message = Some(redact(
+                        format!("Failed to load tools: {}", e),
+                        &self.redact_patterns,
+                    ));
+                }
+            },
+            TaskOutcome::PromptsLoaded(result) => match result {
+                Ok(prompts) => {
+                    warn_duplicates("prompt", prompts.iter().map(|p| p.name.as_str()));
+                    self.last_loaded.insert(Tab::Prompts, Instant::now());
+                    let previous = self
+                        .prompts
+                        .get(self.selected_prompt)
+                        .map(|p| p.name.clone());
+                    self.prompts = prompts;
+                    self.selected_prompt = reselect(
+                        &self.prompts,
+                        previous.as_deref(),
+                        self.selected_prompt,
+                        |p| &p.name,
+                    );
+                }
+                Err(e) => {
+                    self.error_message = Some(redact(
+                        format!("Failed to load prompts: {}", e),
+                        &self.redact_patterns,
+                    ));
+                }
+            },
+            TaskOutcome::ResourcesLoaded(result) => match result {
+                Ok(resources) => {
+                    warn_duplicates("resource URI", resources.iter().map(|r| r.uri.as_str()));
+                    self.last_loaded.insert(Tab::Resources, Instant::now());
+                    // Resources are identified by URI; names need not be unique
+                    let previous = self
+                        .resources
+                        .get(self.selected_resource)
+                        .map(|r| r.uri.clone());
+                    self.resources = resources;
+                    self.selected_resource = reselect(
+                        &self.resources,
+                        previous.as_deref(),
+                        self.selected_resource,
+                        |r| &r.uri,
+                    );
+                }
+                Err(e) => {
+                    self.error_message = Some(redact(
+                        format!("Failed to load resources: {}", e),
+                        &self.redact_patterns,
+                    ));
+                }
+            },
+            TaskOutcome::ServerInfoLoaded(server_info) => {
+                self.server_info = server_info;
+                self.last_loaded.insert(Tab::ServerInfo, Instant::now());
+            }
+            TaskOutcome::Reinitialized { result, lists } => match result {
+                Ok(info) => {
+                    self.server_info = Some(info);
+                    self.last_loaded.insert(Tab::ServerInfo, Instant::now());
+                    if let Some(lists) = lists {
+                        self.apply_task_outcome(TaskOutcome::ListsLoaded(lists));
+                    }
+                }
+                Err(e) => {
+                    self.error_message = Some(redact(
+                        format!("Failed to re-initialize: {:#}", e),
+                        &self.redact_patterns,
+                    ));
+                }
+            },
+            TaskOutcome::ToolCall { tool_name, result } => match result {
+                Ok(result) => {
+                    if let Some(request) = self.pending_cache_request.take() {
+                        self.cache_result(request, CachedResult::ToolCall(result.clone()));
+                    }
+                    // Show result in detail view
+                    let output_schema = self
+                        .tools
+                        .iter()
+                        .find(|t| t.name == tool_name)
+                        .and_then(|t| t.output_schema.as_ref());
+                    let parts = tool_result_parts(&tool_name, &result, output_schema)
+                        .into_iter()
+                        .map(|part| match part {
+                            DetailPart::Text(text) => {
+                                DetailPart::Text(redact(text, &self.redact_patterns))
+                            }
+                            DetailPart::Section { title, body } => DetailPart::Section {
+                                title,
+                                body: redact(body, &self.redact_patterns),
+                            },
+                        })
+                        .collect();
+                    self.tool_call_result = Some(result);
+                    self.detail_view = Some(DetailView::sectioned(parts));
+                    self.item_detail = false;
+                    self.detail_scroll = 0;
+                }
+                Err(e) => {
+                    let error = redact(format!("Tool call failed: {}", e), &self.redact_patterns);
+                    if !self.reopen_failed_tool_call(&tool_name, error.clone()) {
+                        self.error_message = Some(error);
+                    }
+                }
+            },
+            TaskOutcome::PromptGet {
+                prompt_name,
+                result,
+            } => match result {
+                Ok(result) => {
+                    // Show result in detail view
+                    let detail = redact(
+                        format_prompt_result(&prompt_name, &result),
+                        &self.redact_patterns,
+                    );
+                    self.embedded_resources = embedded_resource_uris(&result);
+                    self.prompt_json_arguments = prompt_json_object(&result);
+                    self.prompt_result = Some(result);
+                    self.detail_view = Some(DetailView::new(detail));
+                    self.item_detail = false;
+                    self.detail_scroll = 0;
+                }
+                Err(e) => {
+                    self.error_message = Some(redact(
+                        format!("Prompt get failed: {}", e),
+                        &self.redact_patterns,
+                    ));
+                }
+            },
+            TaskOutcome::ResourceRead {
+                resource_name,
+                uri,
+                result,
+            } => match result {
+                Ok(contents) => {
+                    if let Some(request) = self.pending_cache_request.take() {
+                        self.cache_result(request, CachedResult::ResourceRead(contents.clone()));
+                    }
+                    // Show result in detail view
+                    let detail = redact(
+                        format_resource_read_result(
+                            &resource_name,
+                            &uri,
+                            &contents,
+                            self.settings.hex_dump,
+                        ),
+                        &self.redact_patterns,
+                    );
+                    self.resource_read_result = Some(contents);
+                    self.resource_read_source = Some((resource_name, uri));
+                    self.detail_view = Some(DetailView::new(detail));
+                    self.item_detail = false;
+                    self.detail_scroll = 0;
+                    self.error_message = None; // Clear any previous errors
+                }
+                Err(e) => {
+                    let error_msg = format!("Failed to read resource '{}': {:#}", resource_name, e);
+                    self.error_message = Some(redact(error_msg, &self.redact_patterns));
+                }
+            },
+        }
+    }
+
+    fn cache_result(&mut self, request: CachedRequest, result: CachedResult) {
+        if let Some(cache) = &mut self.result_cache {
+            cache.insert(request.key(), result);
+        }
+        self.cached_request = None;
+    }
+
+    /// Read the `index`th resource embedded in the prompt result being shown.
+    pub fn read_embedded_resource(&mut self, client: &Arc<McpClient>, index: usize) {
+        let Some(uri) = self.embedded_resources.get(index).cloned() else {
+            return;
+        };
+        if self.reject_if_busy() {
+            return;
+        }
+        self.embedded_resources.clear();
+        self.prompt_json_arguments = None;
+        self.spawn_resource_read(client, uri.clone(), uri);
+    }
+
+    pub fn start_debug_log_filter(&mut self) {
+        if self.current_tab != Tab::DebugLogs {
+            return;
+        }
+
+        self.text_prompt = Some(TextPrompt {
+            kind: TextPromptKind::DebugLogFilter,
+            title: "Filter Debug Logs by Request ID (empty: show all)".to_string(),
+            value: self.settings.debug_log_filter.clone().unwrap_or_default(),
+            cursor: usize::MAX,
+        });
+    }
+
+    pub fn cancel_text_prompt(&mut self) {
+        self.text_prompt = None;
+    }
+
+    pub fn submit_text_prompt(&mut self, client: &Arc<McpClient>) {
+        let Some(prompt) = self.text_prompt.take() else {
+            return;
+        };
+        let value = prompt.value.trim().to_string();
+
+        match prompt.kind {
+            TextPromptKind::DebugLogFilter => {
+                self.settings.debug_log_filter = if value.is_empty() { None } else { Some(value) };
+                self.debug_log_scroll = 0;
+                self.problem_mark = None;
+            }
+            TextPromptKind::ResourceUri => {
+                if value.is_empty() || self.reject_if_busy() {
+                    return;
+                }
+                self.spawn_resource_read(client, value.clone(), value);
+            }
+        }
+    }
+
+    /// Debug log entries matching the current request id filter.
+    pub fn visible_debug_logs(&self) -> Vec<&LogEntry> {
+        self.debug_logs
+            .iter()
+            .filter(|entry| match &self.settings.debug_log_filter {
+                Some(filter) => entry.request_id.as_deref() == Some(filter.as_str()),
+                None => true,
+            })
+            .collect()
+    }
+
+    /// Scroll the current logs tab to the next (or previous) error or
+    /// warning, wrapping around at the end.
+    pub fn jump_to_problem(&mut self, forward: bool) {
+        let tab = self.current_tab;
+        let (problems, rows): (Vec<bool>, Vec<usize>) = match tab {
+            Tab::ServerLogs => (
+                self.collapsed_logs()
+                    .iter()
+                    .map(|(line, _)| problem_level(line).is_some())
+                    .collect(),
+                self.log_rows.borrow().clone(),
+            ),
+            Tab::DebugLogs => (
+                self.visible_debug_logs()
+                    .iter()
+                    .map(|entry| matches!(entry.level.as_str(), "ERROR" | "WARN"))
+                    .collect(),
+                self.debug_log_rows.borrow().clone(),
+            ),
+            _ => return,
+        };
+        let len = problems.len();
+        // Lines that arrived since the last frame have no rows yet; until
+        // then they're taken to be one row each
+        let rows: Vec<usize> = (0..len)
+            .map(|i| match rows.get(i) {
+                Some(&row) => row,
+                None => rows.last().map_or(0, |last| last + 1) + i - rows.len(),
+            })
+            .collect();
+        let mark = match self.problem_mark {
+            Some((marked, index)) if marked == tab => Some(index),
+            _ => None,
+        };
+        let Some((scroll, follow, bottom)) = self.log_view() else {
+            return;
+        };
+
+        // Search from the problem landed on last, or else from the line at
+        // the top of the view
+        let top = if *follow { bottom } else { *scroll };
+        let start = mark
+            .unwrap_or_else(|| rows.partition_point(|&row| row <= top).saturating_sub(1))
+            .min(len.saturating_sub(1));
+        let found = (1..=len)
+            .map(|step| {
+                if forward {
+                    (start + step) % len
+                } else {
+                    (start + len - step) % len
+                }
+            })
+            .find(|&i| problems[i]);
+        match found {
+            Some(index) => {
+                *scroll = rows[index];
+                // Stay on the problem as new lines arrive
+                *follow = false;
+                self.problem_mark = Some((tab, index));
+            }
+            None => self.error_message = Some("No errors or warnings in the logs".to_string()),
+        }
+    }
+
+    /// Server log lines with consecutive repeats merged, each with how many
+    /// times it was logged in a row. `logs` keeps every line for export.
+    pub fn collapsed_logs(&self) -> Vec<(&str, usize)> {
+        collapse_repeats(&self.logs)
+    }
+
+    pub fn export_logs(&self) -> Result<String> {
+        let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
+        let extension = match self.log_export_format {
+            LogExportFormat::Jsonl => "jsonl",
+            _ => "json",
+        };
+        let filename = format!("mcpeek_logs_{}.{}", timestamp, extension);
+
+        std::fs::write(&filename, self.format_logs()?)?;
+
+        Ok(filename)
+    }
+
+    /// The logs as written by `export_logs`, in `log_export_format`.
+    fn format_logs(&self) -> Result<String> {
+        if self.log_export_format == LogExportFormat::Jsonl {
+            // Server lines and debug entries, told apart by `source`
+            #[derive(Serialize)]
+            #[serde(tag = "source", rename_all = "lowercase")]
+            enum LogLine<'a> {
+                Server { message: &'a str },
+                Debug(&'a LogEntry),
+            }
+
+            let mut jsonl = String::new();
+            let lines = self
+                .logs
+                .iter()
+                .map(|line| LogLine::Server {
+                    message: line.trim_end_matches('\n'),
+                })
+                .chain(self.debug_logs.iter().map(LogLine::Debug));
+            for line in lines {
+                jsonl.push_str(&serde_json::to_string(&line)?);
+                jsonl.push('\n');
+            }
+            return Ok(jsonl);
+        }
+
+        #[derive(Serialize)]
+        struct LogExport {
+            metadata: ExportMetadata,
+            server_logs: Vec<String>,
+            debug_logs: Vec<LogEntry>,
+        }
+
+        #[derive(Serialize)]
+        struct ExportMetadata {
+            export_timestamp: String,
+            application_version: String,
+            server_log_count: usize,
+            debug_log_count: usize,
+        }
+
+        let export = LogExport {
+            metadata: ExportMetadata {
+                export_timestamp: chrono::Utc::now()
+                    .to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+                application_version: env!("CARGO_PKG_VERSION").to_string(),
                 server_log_count: self.logs.len(),
                 debug_log_count: self.debug_logs.len(),
             },
@@ -753,18 +2768,190 @@ impl App {
             debug_logs: self.debug_logs.clone(),
         };
 
+        Ok(match self.log_export_format {
+            LogExportFormat::Compact => serde_json::to_string(&export)?,
+            _ => serde_json::to_string_pretty(&export)?,
+        })
+    }
+
+    /// Write the server's full `initialize` result to a JSON file.
+    pub fn export_server_info(&self) -> Result<String> {
+        let info = self
+            .server_info
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No server information available"))?;
+
         let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
-        let filename = format!("mcpeek_logs_{}.json", timestamp);
+        let filename = format!("server_info_{}.json", timestamp);
 
-        let json = serde_json::to_string_pretty(&export)?;
+        let json = serde_json::to_string_pretty(info)?;
         std::fs::write(&filename, json)?;
 
         Ok(filename)
     }
 }
 
+fn warn_duplicates<'a>(kind: &str, names: impl IntoIterator<Item = &'a str>) {
+    for name in duplicate_names(names) {
+        warn!("Server lists more than one {} named '{}'", kind, name);
+    }
+}
+
+/// Whether a server log line reports an error or a warning, going by the
+/// words "error" and "warn" appearing anywhere in it.
+pub fn problem_level(line: &str) -> Option<Level> {
+    let line = line.to_lowercase();
+    if line.contains("error") {
+        Some(Level::ERROR)
+    } else if line.contains("warn") {
+        Some(Level::WARN)
+    } else {
+        None
+    }
+}
+
+/// Merge runs of identical lines into one, with the length of the run.
+fn collapse_repeats(lines: &[String]) -> Vec<(&str, usize)> {
+    let mut collapsed: Vec<(&str, usize)> = Vec::new();
+    for line in lines {
+        match collapsed.last_mut() {
+            Some((last, count)) if *last == line.as_str() => *count += 1,
+            _ => collapsed.push((line, 1)),
+        }
+    }
+    collapsed
+}
+
+/// Describe a declared roots capability, e.g. "Yes (supports list changes)".
+pub fn roots_summary(roots: Option<&RootsCapability>) -> &'static str {
+    match roots {
+        Some(roots) if roots.list_changed => "Yes (supports list changes)",
+        Some(_) => "Yes",
+        None => "No",
+    }
+}
+
+/// A configured root as its name and URI, e.g. "src (file:///work/src)".
+pub fn describe_root(root: &Root) -> String {
+    match &root.name {
+        Some(name) => format!("{} ({})", name, root.uri),
+        None => root.uri.clone(),
+    }
+}
+
+/// Regex matching a whole name against a `--select` glob, where `*` matches
+/// any run of characters and `?` any one character.
+pub fn glob_regex(glob: &str) -> std::result::Result<Regex, regex::Error> {
+    let pattern = glob
+        .split('*')
+        .map(|part| {
+            part.split('?')
+                .map(regex::escape)
+                .collect::<Vec<_>>()
+                .join(".")
+        })
+        .collect::<Vec<_>>()
+        .join(".*");
+    Regex::new(&format!("^{}$", pattern))
+}
+
+/// Index of the form field an error message refers to: the longest field
+/// name that appears in the message as a whole word.
+fn error_field_index(fields: &[InputField], message: &str) -> Option<usize> {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    let mentions = |name: &str| {
+        message.match_indices(name).any(|(start, _)| {
+            let before = message[..start].chars().next_back();
+            let after = message[start + name.len()..].chars().next();
+            !before.is_some_and(is_word) && !after.is_some_and(is_word)
+        })
+    };
+
+    fields
+        .iter()
+        .enumerate()
+        .filter(|(_, field)| !field.name.is_empty() && mentions(&field.name))
+        .max_by_key(|(_, field)| field.name.len())
+        .map(|(index, _)| index)
+}
+
+/// Rows `text` occupies when wrapped to `width` columns (roughly: wrapping
+/// is by character count rather than on word boundaries).
+fn wrapped_height(text: &str, width: usize) -> usize {
+    let width = width.max(1);
+    text.split('\n')
+        .map(|line| line.chars().count().div_ceil(width).max(1))
+        .sum()
+}
+
+/// Byte offset of the character at index `cursor`, or the end of `value`.
+fn byte_offset(value: &str, cursor: usize) -> usize {
+    value
+        .char_indices()
+        .nth(cursor)
+        .map(|(i, _)| i)
+        .unwrap_or(value.len())
+}
+
+/// Find the previously selected item in a reloaded list by its identity,
+/// falling back to clamping the old index when the item is gone.
+fn reselect<T>(
+    items: &[T],
+    previous: Option<&str>,
+    index: usize,
+    key: impl Fn(&T) -> &str,
+) -> usize {
+    if let Some(position) = previous.and_then(|id| items.iter().position(|item| key(item) == id)) {
+        return position;
+    }
+    index.min(items.len().saturating_sub(1))
+}
+
+/// Number of parameters the tool's input schema marks as required.
+fn required_param_count(tool: &Tool) -> usize {
+    parse_input_schema(&tool.input_schema)
+        .iter()
+        .filter(|f| f.required)
+        .count()
+}
+
+/// Joins the variants of a field that accepts more than one type, e.g.
+/// `string | number`.
+const UNION_SEPARATOR: &str = " | ";
+
+/// The types a property accepts, from a `type` string or array or from its
+/// `anyOf`/`oneOf` variants. `null` is dropped unless it's the only type, as
+/// an empty field already means no value.
+fn schema_types(prop: &Value) -> Vec<String> {
+    let mut types: Vec<String> = Vec::new();
+    match prop.get("type") {
+        Some(Value::String(t)) => types.push(t.clone()),
+        Some(Value::Array(list)) => {
+            types.extend(list.iter().filter_map(|t| t.as_str().map(String::from)))
+        }
+        _ => {}
+    }
+    for key in ["anyOf", "oneOf"] {
+        if let Some(variants) = prop.get(key).and_then(|v| v.as_array()) {
+            types.extend(variants.iter().flat_map(schema_types));
+        }
+    }
+
+    let mut unique = Vec::new();
+    for t in types {
+        if !unique.contains(&t) {
+            unique.push(t);
+        }
+    }
+    if unique.len() > 1 {
+        unique.retain(|t| t != "null");
+    }
+    unique
+}
+
 fn parse_input_schema(schema: &Value) -> Vec<InputField> {
     let mut fields = Vec::new();
+    let schema = &schema::resolve_refs(schema);
 
     // Handle JSON Schema object
     if let Some(properties) = schema.get("properties").and_then(|p| p.as_object()) {
@@ -779,11 +2966,25 @@ fn parse_input_schema(schema: &Value) -> Vec<InputField> {
             .unwrap_or_default();
 
         for (name, prop) in properties {
-            let field_type = prop
-                .get("type")
-                .and_then(|t| t.as_str())
-                .unwrap_or("string")
-                .to_string();
+            let types = schema_types(prop);
+            let field_type = if !types.is_empty() {
+                types.join(UNION_SEPARATOR)
+            } else if prop.get("anyOf").is_some() || prop.get("oneOf").is_some() {
+                // Variants without a type (e.g. only `const` or `enum`)
+                "any".to_string()
+            } else {
+                "string".to_string()
+            };
+
+            let item_type = if field_type == "array" {
+                prop.get("items")
+                    .and_then(|items| items.get("type"))
+                    .and_then(|t| t.as_str())
+                    .filter(|t| matches!(*t, "string" | "number" | "integer" | "boolean"))
+                    .map(String::from)
+            } else {
+                None
+            };
 
             let description = prop
                 .get("description")
@@ -795,6 +2996,7 @@ fn parse_input_schema(schema: &Value) -> Vec<InputField> {
             fields.push(InputField {
                 name: name.clone(),
                 field_type,
+                item_type,
                 required,
                 description,
             });
@@ -806,7 +3008,107 @@ fn parse_input_schema(schema: &Value) -> Vec<InputField> {
     fields
 }
 
-fn format_tool_result(tool_name: &str, result: &CallToolResult) -> String {
+/// Convert a single form value to JSON according to its schema type.
+fn coerce_input_value(
+    name: &str,
+    field_type: &str,
+    value: &str,
+) -> std::result::Result<Value, String> {
+    match field_type {
+        "number" => parse_number(name, value).map(Value::Number),
+        "integer" => parse_integer(name, value).map(Value::from),
+        "boolean" => match value.to_lowercase().as_str() {
+            "true" | "yes" | "1" => Ok(Value::Bool(true)),
+            "false" | "no" | "0" => Ok(Value::Bool(false)),
+            _ => Err(format!("'{}' must be true or false", name)),
+        },
+        // Try to parse as JSON
+        "array" | "object" => {
+            serde_json::from_str(value).map_err(|_| format!("'{}' must be valid JSON", name))
+        }
+        // No type to go on: take JSON if it parses, otherwise a string
+        "any" => {
+            Ok(serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_string())))
+        }
+        union if union.contains(UNION_SEPARATOR) => coerce_union_value(name, union, value),
+        _ => Ok(Value::String(value.to_string())),
+    }
+}
+
+/// Coerce a value for a field accepting several types, trying each non-string
+/// type in the schema's order and falling back to a string if that's allowed.
+fn coerce_union_value(name: &str, union: &str, value: &str) -> std::result::Result<Value, String> {
+    let types: Vec<&str> = union.split(UNION_SEPARATOR).collect();
+    for field_type in types.iter().filter(|t| !matches!(**t, "string" | "null")) {
+        if let Ok(value) = coerce_input_value(name, field_type, value) {
+            return Ok(value);
+        }
+    }
+    if types.contains(&"string") {
+        return Ok(Value::String(value.to_string()));
+    }
+    Err(format!("'{}' must be one of: {}", name, types.join(", ")))
+}
+
+/// Parse a number, keeping integers exact. Accepts negatives, decimals and
+/// scientific notation, but not NaN or infinities, which JSON can't represent.
+fn parse_number(name: &str, value: &str) -> std::result::Result<serde_json::Number, String> {
+    if let Ok(num) = value.parse::<i64>() {
+        return Ok(num.into());
+    }
+    let num = value
+        .parse::<f64>()
+        .map_err(|_| format!("'{}' must be a number", name))?;
+    serde_json::Number::from_f64(num).ok_or_else(|| format!("'{}' must be a finite number", name))
+}
+
+/// Parse a whole number. Integral values written as decimals or in
+/// scientific notation (`2.0`, `1e3`) are accepted.
+fn parse_integer(name: &str, value: &str) -> std::result::Result<i64, String> {
+    let not_integer = || format!("'{}' must be an integer", name);
+    let num = parse_number(name, value).map_err(|_| not_integer())?;
+    if let Some(num) = num.as_i64() {
+        return Ok(num);
+    }
+    match num.as_f64() {
+        Some(f) if f.fract() == 0.0 && f >= i64::MIN as f64 && f < i64::MAX as f64 => Ok(f as i64),
+        _ => Err(not_integer()),
+    }
+}
+
+/// Parse the raw JSON editor contents into a tool arguments object.
+fn parse_raw_json_arguments(input: &str) -> std::result::Result<HashMap<String, Value>, String> {
+    if input.trim().is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    match serde_json::from_str::<Value>(input) {
+        Ok(Value::Object(map)) => Ok(map.into_iter().collect()),
+        Ok(_) => Err("Arguments must be a JSON object".to_string()),
+        Err(e) => Err(format!("Invalid JSON arguments: {}", e)),
+    }
+}
+
+/// Short human form of how long ago something happened, e.g. "2m ago".
+pub fn format_age(age: Duration) -> String {
+    format!("{} ago", format_duration(age))
+}
+
+/// Whole seconds, minutes or hours and minutes, e.g. "45s", "2m", "1h 5m".
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m", secs / 60),
+        _ => format!("{}h {}m", secs / 3600, secs % 3600 / 60),
+    }
+}
+
+fn tool_result_parts(
+    tool_name: &str,
+    result: &CallToolResult,
+    output_schema: Option<&Value>,
+) -> Vec<DetailPart> {
     let mut output = format!("Tool Call Result: {}\n\n", tool_name);
 
     if result.is_error.unwrap_or(false) {
@@ -816,40 +3118,193 @@ fn format_tool_result(tool_name: &str, result: &CallToolResult) -> String {
     }
 
     output.push_str("Content:\n");
-    for (i, content) in result.content.iter().enumerate() {
-        if i > 0 {
-            output.push_str("\n---\n\n");
+    let mut parts = Vec::new();
+    let count = result.content.len();
+    if count > 1 {
+        // One section per item, so a long result can be scanned by its titles
+        parts.push(DetailPart::Text(std::mem::take(&mut output)));
+        for (i, content) in result.content.iter().enumerate() {
+            let body = format_tool_content(content);
+            let kind = match content {
+                ToolContent::Text { .. } => "text",
+                ToolContent::Image { .. } => "image",
+                ToolContent::Resource { .. } => "resource",
+            };
+            let lines = body.lines().count();
+            parts.push(DetailPart::Section {
+                title: format!(
+                    "[{}/{}] {} ({} line{})",
+                    i + 1,
+                    count,
+                    kind,
+                    lines,
+                    if lines == 1 { "" } else { "s" }
+                ),
+                body,
+            });
         }
-        match content {
-            ToolContent::Text { text } => {
+    } else {
+        for content in &result.content {
+            output.push_str(&format_tool_content(content));
+        }
+    }
+
+    if let Some(structured) = &result.structured_content {
+        output.push_str("\n\nStructured Content:\n");
+        output.push_str(&serde_json::to_string_pretty(structured).unwrap_or_default());
+        // Check the payload against the schema the tool advertised
+        if let Some(schema) = output_schema {
+            let errors = schema::validate(schema, structured);
+            if errors.is_empty() {
+                output.push_str("\n\nOutput Schema: valid");
+            } else {
+                output.push_str("\n\nOutput Schema: INVALID");
+                for error in errors {
+                    output.push_str(&format!("\n  - {}", error));
+                }
+            }
+        }
+    } else if output_schema.is_some() && !result.is_error.unwrap_or(false) {
+        output.push_str("\n\nOutput Schema: no structured content returned");
+    }
+
+    if parts.is_empty() {
+        parts.push(DetailPart::Text(output));
+    } else if !output.is_empty() {
+        // The last section already ends with a blank line
+        parts.push(DetailPart::Text(
+            output.trim_start_matches('\n').to_string(),
+        ));
+    }
+    parts
+}
+
+/// One content item of a tool result.
+fn format_tool_content(content: &ToolContent) -> String {
+    match content {
+        ToolContent::Text { text } => text.clone(),
+        ToolContent::Image { data, mime_type } => {
+            format!("[Image: {} ({} bytes)]\n", mime_type, data.len())
+        }
+        ToolContent::Resource { resource } => match resource {
+            ResourceContents::Text {
+                uri,
+                text,
+                mime_type,
+            } => {
+                let mut output = format!("[Resource: {}]\n", uri);
+                if let Some(mt) = mime_type {
+                    output.push_str(&format!("MIME Type: {}\n\n", mt));
+                }
                 output.push_str(text);
+                output
             }
-            ToolContent::Image { data, mime_type } => {
-                output.push_str(&format!("[Image: {} ({} bytes)]\n", mime_type, data.len()));
-            }
-            ToolContent::Resource { resource } => match resource {
-                ResourceContents::Text {
-                    uri,
-                    text,
-                    mime_type,
-                } => {
-                    output.push_str(&format!("[Resource: {}]\n", uri));
-                    if let Some(mt) = mime_type {
-                        output.push_str(&format!("MIME Type: {}\n\n", mt));
-                    }
-                    output.push_str(text);
+            ResourceContents::Blob { uri, mime_type, .. } => {
+                let mut output = format!("[Binary Resource: {}]\n", uri);
+                if let Some(mt) = mime_type {
+                    output.push_str(&format!("MIME Type: {}\n", mt));
                 }
-                ResourceContents::Blob { uri, mime_type, .. } => {
-                    output.push_str(&format!("[Binary Resource: {}]\n", uri));
-                    if let Some(mt) = mime_type {
-                        output.push_str(&format!("MIME Type: {}\n", mt));
-                    }
+                output
+            }
+        },
+    }
+}
+
+/// The prompt messages' content items, in order.
+fn prompt_contents(result: &GetPromptResult) -> impl Iterator<Item = &PromptContent> {
+    result
+        .messages
+        .iter()
+        .flat_map(|message| match &message.content {
+            PromptMessageContent::Single(content) => std::slice::from_ref(content),
+            PromptMessageContent::Multiple(contents) => contents.as_slice(),
+        })
+}
+
+/// URIs of the resources embedded in a prompt result, in order.
+fn embedded_resource_uris(result: &GetPromptResult) -> Vec<String> {
+    prompt_contents(result)
+        .filter_map(|content| match content {
+            PromptContent::Resource { resource } => Some(match resource {
+                ResourceContents::Text { uri, .. } | ResourceContents::Blob { uri, .. } => {
+                    uri.clone()
                 }
-            },
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The first JSON object in the prompt result's text, looking in fenced code
+/// blocks first and then at each whole text.
+fn prompt_json_object(result: &GetPromptResult) -> Option<serde_json::Map<String, Value>> {
+    let texts: Vec<&str> = prompt_contents(result)
+        .filter_map(|content| match content {
+            PromptContent::Text { text } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect();
+    let parse_object = |text: &str| match serde_json::from_str(text.trim()) {
+        Ok(Value::Object(object)) => Some(object),
+        _ => None,
+    };
+    texts
+        .iter()
+        .flat_map(|text| fenced_blocks(text))
+        .find_map(parse_object)
+        .or_else(|| texts.iter().copied().find_map(parse_object))
+}
+
+/// Contents of the ``` fenced code blocks in `text`, without the info string.
+fn fenced_blocks(text: &str) -> Vec<&str> {
+    let mut blocks = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("```") {
+        let after = &rest[start + 3..];
+        // Skip the language tag on the opening line
+        let Some(newline) = after.find('\n') else {
+            break;
+        };
+        let body = &after[newline + 1..];
+        let Some(end) = body.find("```") else {
+            break;
+        };
+        blocks.push(&body[..end]);
+        rest = &body[end + 3..];
+    }
+    blocks
+}
+
+/// Pick the tool for a JSON object of arguments: the one it names with
+/// `{"name": ..., "arguments": {...}}` (or `"tool"`), otherwise the selected
+/// tool or the first one taking every key as a parameter.
+fn match_tool_arguments(
+    tools: &[Tool],
+    selected: usize,
+    object: serde_json::Map<String, Value>,
+) -> Option<(usize, serde_json::Map<String, Value>)> {
+    let named = object
+        .get("name")
+        .or_else(|| object.get("tool"))
+        .and_then(Value::as_str);
+    if let (Some(name), Some(Value::Object(arguments))) = (named, object.get("arguments")) {
+        if let Some(index) = tools.iter().position(|t| t.name == name) {
+            return Some((index, arguments.clone()));
         }
     }
 
-    output
+    let takes_all = |tool: &Tool| {
+        let fields = parse_input_schema(&tool.input_schema);
+        object
+            .keys()
+            .all(|key| fields.iter().any(|field| field.name == *key))
+    };
+    let index = if tools.get(selected).is_some_and(takes_all) {
+        selected
+    } else {
+        tools.iter().position(takes_all)?
+    };
+    Some((index, object))
 }
 
 fn format_prompt_result(prompt_name: &str, result: &GetPromptResult) -> String {
@@ -885,6 +3340,18 @@ fn format_prompt_result(prompt_name: &str, result: &GetPromptResult) -> String {
         output.push('\n');
     }
 
+    let embedded = embedded_resource_uris(result);
+    if !embedded.is_empty() {
+        output.push_str("\n---\n\nEmbedded resources (press the number to read one):\n");
+        for (i, uri) in embedded.iter().enumerate().take(9) {
+            output.push_str(&format!("  [{}] {}\n", i + 1, uri));
+        }
+    }
+
+    if prompt_json_object(result).is_some() {
+        output.push_str("\n---\n\nFound a JSON object; press A to use it as tool arguments\n");
+    }
+
     output
 }
 
@@ -922,6 +3389,7 @@ fn format_resource_read_result(
     resource_name: &str,
     uri: &str,
     contents: &[ResourceContents],
+    hex_dump: bool,
 ) -> String {
     let mut output = format!(
         "Resource Read Result: {}\n\nURI: {}\n\n",
@@ -950,8 +3418,9 @@ fn format_resource_read_result(
                     output.push_str(&format!("URI: {}\n", content_uri));
                 }
                 if let Some(mt) = mime_type {
-                    output.push_str(&format!("MIME Type: {}\n\n", mt));
+                    output.push_str(&format!("MIME Type: {}\n", mt));
                 }
+                output.push_str(&format!("Text: {}\n\n", text_stats(text)));
                 output.push_str(text);
             }
             ResourceContents::Blob {
@@ -967,9 +3436,1108 @@ fn format_resource_read_result(
                     output.push_str(&format!("MIME Type: {}\n", mt));
                 }
                 output.push_str(&format!("Size: {} bytes (base64 encoded)\n", blob.len()));
+                if hex_dump {
+                    match BASE64_STANDARD.decode(blob.trim()) {
+                        Ok(bytes) => {
+                            output.push('\n');
+                            output.push_str(&format_hex_dump(&bytes));
+                        }
+                        Err(e) => {
+                            output.push_str(&format!("(can't show hex dump: {})\n", e));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    output
+}
+
+/// Sizes and encoding of a text resource, for telling a resource that was
+/// cut off or mis-decoded by the server from one that's meant to be that way.
+fn text_stats(text: &str) -> String {
+    let plural = |n: usize, what: &str| format!("{} {}{}", n, what, if n == 1 { "" } else { "s" });
+    let mut encoding = if text.is_ascii() { "ASCII" } else { "UTF-8" }.to_string();
+    if text.starts_with('\u{feff}') {
+        encoding.push_str(" with BOM");
+    }
+    // JSON strings are always valid UTF-8 by the time they get here, so
+    // bytes the server couldn't decode only show up as U+FFFD
+    let replaced = text.matches('\u{fffd}').count();
+    if replaced > 0 {
+        encoding.push_str(&format!(
+            ", {} (U+FFFD)",
+            plural(replaced, "replacement character")
+        ));
+    }
+    format!(
+        "{}, {}, {}, {}",
+        plural(text.chars().count(), "char"),
+        plural(text.len(), "byte"),
+        plural(text.lines().count(), "line"),
+        encoding
+    )
+}
+
+/// Classic hex dump (offset, 16 hex bytes, ASCII gutter) of the first
+/// `HEX_DUMP_LIMIT` bytes.
+fn format_hex_dump(bytes: &[u8]) -> String {
+    const HEX_DUMP_LIMIT: usize = 4096;
+
+    let mut output = String::new();
+    for (row, chunk) in bytes[..bytes.len().min(HEX_DUMP_LIMIT)]
+        .chunks(16)
+        .enumerate()
+    {
+        let mut hex = String::new();
+        for (i, byte) in chunk.iter().enumerate() {
+            if i == 8 {
+                hex.push(' ');
             }
+            hex.push_str(&format!("{:02x} ", byte));
         }
+        let ascii: String = chunk
+            .iter()
+            .map(|&b| {
+                if b.is_ascii_graphic() || b == b' ' {
+                    b as char
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+        output.push_str(&format!("{:08x}  {:<49} |{}|\n", row * 16, hex, ascii));
     }
 
+    if bytes.len() > HEX_DUMP_LIMIT {
+        output.push_str(&format!(
+            "(truncated: showing {} of {} bytes)\n",
+            HEX_DUMP_LIMIT,
+            bytes.len()
+        ));
+    }
     output
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_raw_json_arguments() {
+        let arguments = parse_raw_json_arguments(r#"{"q": "rust", "limit": 5}"#).unwrap();
+        assert_eq!(arguments["q"], json!("rust"));
+        assert_eq!(arguments["limit"], json!(5));
+
+        // An empty editor means no arguments
+        assert!(parse_raw_json_arguments("").unwrap().is_empty());
+        assert!(parse_raw_json_arguments("  \n").unwrap().is_empty());
+
+        for input in ["[1, 2]", "\"q\"", "null"] {
+            assert_eq!(
+                parse_raw_json_arguments(input).unwrap_err(),
+                "Arguments must be a JSON object"
+            );
+        }
+        assert!(parse_raw_json_arguments(r#"{"q": }"#)
+            .unwrap_err()
+            .starts_with("Invalid JSON arguments: "));
+    }
+
+    #[test]
+    fn test_reselect_after_reload() {
+        let list =
+            |names: &[&str]| -> Vec<String> { names.iter().map(|name| name.to_string()).collect() };
+        // The same item is followed to its new position
+        assert_eq!(
+            reselect(&list(&["c", "a", "b"]), Some("b"), 1, String::as_str),
+            2
+        );
+        // A vanished item leaves the index, kept within the list
+        assert_eq!(
+            reselect(&list(&["a", "c"]), Some("b"), 1, String::as_str),
+            1
+        );
+        assert_eq!(reselect(&list(&["a"]), Some("b"), 3, String::as_str), 0);
+        assert_eq!(reselect(&list(&[]), Some("b"), 2, String::as_str), 0);
+
+        let prompts = |names: &[&str]| -> Vec<Prompt> {
+            names
+                .iter()
+                .map(|name| serde_json::from_value(json!({"name": name})).unwrap())
+                .collect()
+        };
+        let mut app = App::new(false);
+        app.apply_task_outcome(TaskOutcome::PromptsLoaded(Ok(prompts(&["a", "b", "c"]))));
+        app.selected_prompt = 1;
+        app.apply_task_outcome(TaskOutcome::PromptsLoaded(Ok(prompts(&["new", "a", "b"]))));
+        assert_eq!(app.prompts[app.selected_prompt].name, "b");
+        app.apply_task_outcome(TaskOutcome::PromptsLoaded(Ok(Vec::new())));
+        assert_eq!(app.selected_prompt, 0);
+    }
+
+    /// App with a tool call form of one string field holding `value`.
+    fn form_with_value(value: &str) -> App {
+        let mut app = App::new(false);
+        app.tool_call_input_mode = true;
+        app.input_fields = parse_input_schema(&json!({
+            "type": "object",
+            "properties": {"text": {"type": "string"}}
+        }));
+        app.tool_call_inputs
+            .insert("text".to_string(), value.to_string());
+        app
+    }
+
+    #[test]
+    fn test_edit_multibyte_input_mid_value() {
+        let mut app = form_with_value("héllo");
+        let value = |app: &App| app.tool_call_inputs["text"].clone();
+
+        // The cursor starts at the end and counts characters, not bytes
+        for _ in 0..3 {
+            app.move_cursor_left();
+        }
+        app.update_current_input('ü');
+        assert_eq!(value(&app), "héüllo");
+        app.delete_current_input();
+        app.delete_current_input();
+        assert_eq!(value(&app), "hllo");
+        app.delete_input_forward();
+        assert_eq!(value(&app), "hlo");
+
+        app.move_cursor_home();
+        app.paste_input("ßé");
+        assert_eq!(value(&app), "ßéhlo");
+        app.move_cursor_end();
+        app.move_cursor_right();
+        app.delete_current_input();
+        assert_eq!(value(&app), "ßéhl");
+        assert_eq!(app.input_cursors["text"], 4);
+    }
+
+    #[test]
+    fn test_delete_word_before_cursor() {
+        let mut app = form_with_value("call my tool  ");
+        let value = |app: &App| app.tool_call_inputs["text"].clone();
+
+        // Trailing spaces go with the word before them
+        app.delete_word_before_cursor();
+        assert_eq!(value(&app), "call my ");
+
+        // In mid-word, only the part before the cursor goes
+        app.move_cursor_left();
+        app.move_cursor_left();
+        app.delete_word_before_cursor();
+        assert_eq!(value(&app), "call y ");
+        assert_eq!(app.input_cursors["text"], 5);
+
+        // Nothing to delete at the start
+        app.move_cursor_home();
+        app.delete_word_before_cursor();
+        assert_eq!(value(&app), "call y ");
+        assert_eq!(app.input_cursors["text"], 0);
+
+        // Ctrl+U clears it all, leaving the cursor at the start
+        app.move_cursor_end();
+        app.clear_current_input();
+        assert_eq!(value(&app), "");
+        assert_eq!(app.input_cursors["text"], 0);
+        app.update_current_input('x');
+        assert_eq!(value(&app), "x");
+    }
+
+    #[test]
+    fn test_failed_call_reopens_form_on_field() {
+        let mut app = App::new(false);
+        app.tools = serde_json::from_value(json!([{"name": "search", "inputSchema": {
+            "type": "object",
+            "properties": {
+                "query": {"type": "string"},
+                "query_limit": {"type": "integer"},
+                "limit": {"type": "integer"}
+            }
+        }}]))
+        .unwrap();
+        app.tool_call_inputs = [("query", "rust"), ("limit", "-1")]
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let inputs = app.tool_call_inputs.clone();
+        app.input_fields = parse_input_schema(&app.tools[0].input_schema);
+        app.input_field_index = app
+            .input_fields
+            .iter()
+            .position(|f| f.name == "query")
+            .unwrap();
+
+        app.apply_task_outcome(TaskOutcome::ToolCall {
+            tool_name: "search".to_string(),
+            result: Err(anyhow::anyhow!(
+                "MCP error -32602: Invalid params: limit must be positive"
+            )),
+        });
+
+        // The form is back with what was typed, on the field the error names
+        // (as a whole word, so not query_limit)
+        assert!(app.tool_call_input_mode);
+        assert_eq!(app.tool_call_inputs, inputs);
+        assert_eq!(app.input_fields[app.input_field_index].name, "limit");
+        assert_eq!(app.tool_call_error_field.as_deref(), Some("limit"));
+        assert!(app.error_message.is_none());
+    }
+
+    #[test]
+    fn test_build_tool_arguments_from_comma_separated_lists() {
+        let mut app = App::new(false);
+        app.input_fields = parse_input_schema(&json!({
+            "type": "object",
+            "properties": {
+                "tags": {"type": "array", "items": {"type": "string"}},
+                "sizes": {"type": "array", "items": {"type": "number"}},
+                "flags": {"type": "array", "items": {"type": "boolean"}}
+            }
+        }));
+        let set = |app: &mut App, name: &str, value: &str| {
+            app.tool_call_inputs
+                .insert(name.to_string(), value.to_string());
+        };
+
+        // Items are trimmed and empty ones skipped
+        set(&mut app, "tags", "a, ,b");
+        set(&mut app, "sizes", "1, 2.5");
+        set(&mut app, "flags", "true,no");
+        let arguments = app.build_tool_arguments().unwrap();
+        assert_eq!(arguments["tags"], json!(["a", "b"]));
+        assert_eq!(arguments["sizes"], json!([1, 2.5]));
+        assert_eq!(arguments["flags"], json!([true, false]));
+
+        // A leading `[` is taken as JSON
+        set(&mut app, "tags", r#"["a, b", "c"]"#);
+        assert_eq!(
+            app.build_tool_arguments().unwrap()["tags"],
+            json!(["a, b", "c"])
+        );
+        set(&mut app, "tags", "[a");
+        assert_eq!(
+            app.build_tool_arguments().unwrap_err(),
+            "'tags' must be valid JSON"
+        );
+
+        // A bad item names its field
+        set(&mut app, "tags", "a");
+        set(&mut app, "sizes", "1, big");
+        assert_eq!(
+            app.build_tool_arguments().unwrap_err(),
+            "'sizes' must be a number"
+        );
+    }
+
+    #[test]
+    fn test_format_hex_dump() {
+        let dump = format_hex_dump(b"Hello, world!\x00\x01\xffAB");
+        let lines: Vec<&str> = dump.lines().collect();
+        assert_eq!(
+            lines[0],
+            "00000000  48 65 6c 6c 6f 2c 20 77  6f 72 6c 64 21 00 01 ff  |Hello, world!...|"
+        );
+        // A short last row is padded so the ASCII column lines up
+        assert_eq!(lines[1], format!("00000010  41 42 {:43} |AB|", ""));
+        assert_eq!(lines[0].find('|'), lines[1].find('|'));
+        assert_eq!(lines.len(), 2);
+
+        let dump = format_hex_dump(&[0; 4096]);
+        assert_eq!(dump.lines().count(), 256);
+        assert!(!dump.contains("truncated"));
+
+        let dump = format_hex_dump(&[0; 5000]);
+        let lines: Vec<&str> = dump.lines().collect();
+        assert_eq!(lines.len(), 257);
+        assert!(lines[255].starts_with("00000ff0  "));
+        assert_eq!(lines[256], "(truncated: showing 4096 of 5000 bytes)");
+    }
+
+    #[test]
+    fn test_detail_view_truncation() {
+        let row = |detail: &DetailView, row: usize| detail.rows(row, row + 1)[0].text.to_string();
+        // 4096 lines of 64 bytes fill the limit exactly
+        let line = format!("{}\n", "a".repeat(63));
+        let text = line.repeat(4096);
+        assert_eq!(text.len(), DETAIL_TRUNCATE_BYTES);
+        let detail = DetailView::new(text.clone());
+        assert!(!detail.is_truncated());
+        assert_eq!(detail.line_count(), 4096);
+
+        // One byte over cuts it off, but keeps everything for F and W
+        let text = format!("{}b", text);
+        let detail = DetailView::new(text.clone());
+        assert!(detail.is_truncated());
+        assert_eq!(detail.line_count(), 4098);
+        assert_eq!(
+            row(&detail, 4097),
+            "(truncated: showing 256 KB of 256 KB - press F to view in full or W to save to a file)"
+        );
+        assert_eq!(detail.text(), text);
+
+        // A character straddling the limit is left out whole
+        let text = format!("{}{}é", line.repeat(4095), "a".repeat(63));
+        assert_eq!(text.len(), DETAIL_TRUNCATE_BYTES + 1);
+        let mut app = App::new(false);
+        app.detail_view = Some(DetailView::new(text.clone()));
+        let detail = app.detail_view.as_ref().unwrap();
+        assert!(detail.is_truncated());
+        assert_eq!(row(detail, 4095), "a".repeat(63));
+
+        app.show_full_detail();
+        let detail = app.detail_view.as_ref().unwrap();
+        assert!(!detail.is_truncated());
+        assert_eq!(detail.line_count(), 4096);
+        assert_eq!(row(detail, 4095), format!("{}é", "a".repeat(63)));
+    }
+
+    #[test]
+    fn test_only_read_only_requests_are_cacheable() {
+        let mut app = App::new(false);
+        app.tools = serde_json::from_value(json!([
+            {"name": "get", "inputSchema": {}, "annotations": {"readOnlyHint": true}},
+            {"name": "put", "inputSchema": {}, "annotations": {"readOnlyHint": false}},
+            {"name": "other", "inputSchema": {}}
+        ]))
+        .unwrap();
+        let call = |name: &str| CachedRequest::ToolCall {
+            tool_name: name.to_string(),
+            arguments: None,
+        };
+        let read = CachedRequest::ResourceRead {
+            resource_name: "A".to_string(),
+            uri: "file:///a.txt".to_string(),
+        };
+
+        // Nothing is cached without --cache
+        assert!(!app.is_cacheable(&call("get")));
+        assert!(!app.is_cacheable(&read));
+
+        app.result_cache = Some(ResultCache::new(4));
+        assert!(app.is_cacheable(&call("get")));
+        assert!(app.is_cacheable(&read));
+        for name in ["put", "other", "unknown"] {
+            assert!(!app.is_cacheable(&call(name)), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_tab_order() {
+        let tabs = Tab::defaults(false);
+        assert_eq!(
+            tabs,
+            [
+                Tab::Tools,
+                Tab::Prompts,
+                Tab::Resources,
+                Tab::ServerInfo,
+                Tab::ServerLogs
+            ]
+        );
+        assert_eq!(Tab::defaults(true).last(), Some(&Tab::DebugLogs));
+        assert_eq!(Tab::Tools.next(&tabs), Tab::Prompts);
+        assert_eq!(Tab::ServerLogs.next(&tabs), Tab::Tools);
+        assert_eq!(Tab::Tools.previous(&tabs), Tab::ServerLogs);
+
+        assert_eq!("Info".parse::<Tab>(), Ok(Tab::ServerInfo));
+        assert_eq!(" server-logs ".parse::<Tab>(), Ok(Tab::ServerLogs));
+        assert_eq!("debug".parse::<Tab>(), Ok(Tab::DebugLogs));
+        assert!("history"
+            .parse::<Tab>()
+            .unwrap_err()
+            .contains("unknown tab 'history'"));
+    }
+
+    #[test]
+    fn test_set_tabs() {
+        let mut app = App::new(false);
+        app.current_tab = Tab::ServerLogs;
+
+        // Order is as given, without repeats or the debug tab outside debug
+        // mode, and the current tab moves to the first one when dropped
+        app.set_tabs(&[Tab::Resources, Tab::DebugLogs, Tab::Tools, Tab::Resources]);
+        assert_eq!(app.tabs, [Tab::Resources, Tab::Tools]);
+        assert_eq!(app.current_tab, Tab::Resources);
+
+        // Switching wraps around within the subset
+        app.next_tab();
+        assert_eq!(app.current_tab, Tab::Tools);
+        app.next_tab();
+        assert_eq!(app.current_tab, Tab::Resources);
+        app.previous_tab();
+        assert_eq!(app.current_tab, Tab::Tools);
+
+        // A tab outside the subset goes to the first one
+        assert_eq!(Tab::Prompts.next(&app.tabs), Tab::Resources);
+
+        // Nothing left keeps the tabs as they were
+        app.set_tabs(&[Tab::DebugLogs]);
+        assert_eq!(app.tabs, [Tab::Resources, Tab::Tools]);
+    }
+
+    #[test]
+    fn test_tool_sort_orders() {
+        let required = |names: &[&str]| {
+            json!({
+                "type": "object",
+                "properties": {"a": {}, "b": {}},
+                "required": names
+            })
+        };
+        let tools: Vec<Tool> = serde_json::from_value(json!([
+            {"name": "Zeta", "description": "beta things", "inputSchema": required(&["a"]),
+             "annotations": {"destructiveHint": true}},
+            {"name": "alpha", "inputSchema": required(&[]),
+             "annotations": {"readOnlyHint": true}},
+            {"name": "Mid", "description": "Alpha stuff", "inputSchema": required(&["a", "b"]),
+             "annotations": {"idempotentHint": true}},
+            {"name": "beta", "description": "gamma", "inputSchema": required(&[])}
+        ]))
+        .unwrap();
+        let mut app = App::new(false);
+        app.apply_task_outcome(TaskOutcome::ToolsLoaded(Ok(tools)));
+        app.selected_tool = 3;
+
+        for (sort, expected) in [
+            (ToolSort::Name, ["alpha", "beta", "Mid", "Zeta"]),
+            // Destructive, then read-only, then idempotent
+            (ToolSort::Annotations, ["Zeta", "alpha", "Mid", "beta"]),
+            // Most required parameters first, ties in the server's order
+            (ToolSort::Required, ["Mid", "Zeta", "alpha", "beta"]),
+            // Case-insensitive, with no description last
+            (ToolSort::Description, ["Mid", "Zeta", "beta", "alpha"]),
+        ] {
+            app.set_tool_sort(None);
+            app.set_tool_sort(Some(sort));
+            let names: Vec<&str> = app.tools.iter().map(|t| t.name.as_str()).collect();
+            assert_eq!(names, expected, "{:?}", sort);
+            assert_eq!(app.tools[app.selected_tool].name, "beta", "{:?}", sort);
+        }
+
+        app.set_tool_sort(None);
+        let names: Vec<&str> = app.tools.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["Zeta", "alpha", "Mid", "beta"]);
+        assert_eq!(app.selected_tool, 3);
+    }
+
+    #[test]
+    fn test_initial_tool_call_opens_prefilled_form() {
+        let tools: Vec<Tool> = serde_json::from_value(json!([
+            {"name": "list", "inputSchema": {"type": "object"}},
+            {"name": "search", "inputSchema": {
+                "type": "object",
+                "properties": {"q": {"type": "string"}}
+            }}
+        ]))
+        .unwrap();
+        let mut app = App::new(false);
+        app.current_tab = Tab::Prompts;
+        app.initial_tool_call = Some(InitialToolCall {
+            tool_name: "search".to_string(),
+            arguments: vec![
+                ("q".to_string(), "rust".to_string()),
+                ("limit".to_string(), "5".to_string()),
+            ],
+            execute: true,
+        });
+        app.apply_task_outcome(TaskOutcome::ToolsLoaded(Ok(tools)));
+
+        assert_eq!(app.current_tab, Tab::Tools);
+        assert_eq!(app.selected_tool, 1);
+        assert!(app.tool_call_input_mode);
+        assert_eq!(
+            app.tool_call_inputs.get("q").map(String::as_str),
+            Some("rust")
+        );
+        assert!(!app.tool_call_inputs.contains_key("limit"));
+        assert_eq!(
+            app.tool_call_error.as_deref(),
+            Some("'search' has no parameter named limit")
+        );
+        assert!(app.initial_tool_call.is_none());
+        // Not run with an unknown argument, even with `--once`
+        assert!(!app.run_initial_tool_call);
+    }
+
+    #[test]
+    fn test_recent_tools_sort_first() {
+        let tools: Vec<Tool> = serde_json::from_value(json!([
+            {"name": "a", "inputSchema": {"type": "object"}},
+            {"name": "b", "inputSchema": {"type": "object"}},
+            {"name": "c", "inputSchema": {"type": "object"}},
+            {"name": "d", "inputSchema": {"type": "object"}}
+        ]))
+        .unwrap();
+        let mut app = App::new(false);
+        app.apply_task_outcome(TaskOutcome::ToolsLoaded(Ok(tools)));
+        let now = Instant::now();
+        app.tool_last_used.insert("c".to_string(), now);
+        app.tool_last_used
+            .insert("b".to_string(), now + Duration::from_secs(1));
+
+        app.toggle_recent_tools();
+        let names: Vec<&str> = app.tools.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["b", "c", "a", "d"]);
+        // Still on the tool that was selected before sorting
+        assert_eq!(app.tools[app.selected_tool].name, "a");
+
+        app.toggle_recent_tools();
+        let names: Vec<&str> = app.tools.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn test_glob_regex() {
+        let re = glob_regex("github_*").unwrap();
+        assert!(re.is_match("github_create_issue"));
+        assert!(re.is_match("github_"));
+        assert!(!re.is_match("my_github_tool"));
+
+        let re = glob_regex("get_?.v1").unwrap();
+        assert!(re.is_match("get_a.v1"));
+        assert!(!re.is_match("get_ab.v1"));
+        assert!(!re.is_match("get_a_v1"));
+    }
+
+    #[test]
+    fn test_tool_select() {
+        let mut app = App::new(false);
+        app.tool_select = vec![glob_regex("fs_*").unwrap(), glob_regex("echo").unwrap()];
+        let tools: Vec<Tool> = serde_json::from_value(json!([
+            {"name": "fs_read", "inputSchema": {}},
+            {"name": "net_get", "inputSchema": {}},
+            {"name": "echo", "inputSchema": {}},
+            {"name": "echo2", "inputSchema": {}},
+            {"name": "fs_write", "inputSchema": {}}
+        ]))
+        .unwrap();
+        app.apply_task_outcome(TaskOutcome::ToolsLoaded(Ok(tools)));
+
+        let names: Vec<_> = app.tools.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["fs_read", "echo", "fs_write"]);
+        assert_eq!(app.unselected_tools, 2);
+    }
+
+    #[test]
+    fn test_text_stats() {
+        assert_eq!(text_stats(""), "0 chars, 0 bytes, 0 lines, ASCII");
+        assert_eq!(text_stats("a\nb\n"), "4 chars, 4 bytes, 2 lines, ASCII");
+        assert_eq!(text_stats("café"), "4 chars, 5 bytes, 1 line, UTF-8");
+        assert_eq!(
+            text_stats("\u{feff}x\u{fffd}"),
+            "3 chars, 7 bytes, 1 line, UTF-8 with BOM, 1 replacement character (U+FFFD)"
+        );
+
+        let contents = vec![ResourceContents::Text {
+            uri: "file:///a.txt".to_string(),
+            text: "hello\n".to_string(),
+            mime_type: Some("text/plain".to_string()),
+        }];
+        let output = format_resource_read_result("a", "file:///a.txt", &contents, false);
+        assert!(output
+            .contains("MIME Type: text/plain\nText: 6 chars, 6 bytes, 1 line, ASCII\n\nhello\n"));
+    }
+
+    #[test]
+    fn test_update_connection() {
+        let mut app = App::new(false);
+        assert!(!app.update_connection(false));
+        assert!(!app.disconnected);
+
+        assert!(app.update_connection(true));
+        assert!(app.disconnected);
+        assert!(app.error_message.is_some());
+
+        // Reported once, so a dismissed error stays dismissed
+        app.error_message = None;
+        assert!(!app.update_connection(true));
+        assert!(app.error_message.is_none());
+    }
+
+    #[test]
+    fn test_input_popup() {
+        let mut app = App::new(false);
+        app.input_fields = ["a", "b"]
+            .iter()
+            .map(|name| InputField {
+                name: name.to_string(),
+                field_type: "string".to_string(),
+                item_type: None,
+                required: false,
+                description: None,
+            })
+            .collect();
+        app.tool_call_input_mode = true;
+        let popup = app.input_popup(120, 40);
+        assert_eq!((popup.width, popup.height, popup.error_rows), (80, 14, 0));
+
+        // A 100 character error wraps to two rows of the 76 wide content
+        app.tool_call_error = Some("x".repeat(100));
+        let popup = app.input_popup(120, 40);
+        assert_eq!((popup.height, popup.error_rows), (17, 3));
+        app.raw_json_mode = true;
+        assert_eq!(app.input_popup(120, 40).height, 27);
+
+        // The prompt form has neither an inline error nor a raw JSON editor
+        app.tool_call_input_mode = false;
+        app.prompt_input_mode = true;
+        let popup = app.input_popup(120, 40);
+        assert_eq!((popup.height, popup.error_rows), (14, 0));
+    }
+
+    #[test]
+    fn test_jump_to_problem() {
+        let mut app = App::new(false);
+        app.current_tab = Tab::ServerLogs;
+        app.logs = [
+            "starting\n",
+            "a long line that wraps\n",
+            "ERROR: first\n",
+            "working\n",
+            "WARN: second\n",
+            "done\n",
+        ]
+        .iter()
+        .map(|line| line.to_string())
+        .collect();
+        // As rendered: the second line wraps to three rows
+        app.log_rows.replace(vec![0, 1, 4, 5, 6, 7]);
+        app.log_bottom.set(5);
+        assert!(app.log_follow);
+
+        // Following, the search starts from the line at the bottom of the
+        // view, and lands on the wrapped row the problem starts on
+        app.jump_to_problem(true);
+        assert!(!app.log_follow);
+        assert_eq!(app.log_scroll, 6);
+        assert_eq!(app.problem_mark, Some((Tab::ServerLogs, 4)));
+
+        // Searches continue from the problem landed on, wrapping around
+        app.jump_to_problem(true);
+        assert_eq!(app.log_scroll, 4);
+        assert_eq!(app.problem_mark, Some((Tab::ServerLogs, 2)));
+        app.jump_to_problem(false);
+        assert_eq!(app.log_scroll, 6);
+
+        // Scrolling clears the mark, so the next search starts from the
+        // line at the top of the view, here partway through a wrapped line
+        app.scroll_logs_up(4);
+        assert_eq!(app.problem_mark, None);
+        assert_eq!(app.log_scroll, 1);
+        app.jump_to_problem(true);
+        assert_eq!(app.log_scroll, 4);
+    }
+
+    #[test]
+    fn test_save_detail_keeps_text_unchanged() {
+        // A long line is split for display, but saved as one
+        let text = format!("{{\"data\":\"{}\"}}\r\n", "x".repeat(5000));
+        let mut app = App::new(false);
+        app.detail_view = Some(DetailView::new(text.clone()));
+        assert_eq!(app.detail_view.as_ref().unwrap().line_count(), 2);
+
+        let filename = app.save_detail().unwrap();
+        let saved = std::fs::read(&filename);
+        std::fs::remove_file(&filename).unwrap();
+        assert_eq!(saved.unwrap(), text.as_bytes());
+    }
+
+    #[test]
+    fn test_log_follow() {
+        let mut app = App::new(false);
+        app.current_tab = Tab::ServerLogs;
+        app.logs = (0..30).map(|i| format!("line {}\n", i)).collect();
+        app.log_bottom.set(20);
+        assert!(app.log_follow);
+
+        // Scrolling up starts from the bottom the view was following
+        app.previous_item();
+        assert!(!app.log_follow);
+        assert_eq!(app.log_scroll, 19);
+        app.page_up();
+        assert_eq!(app.log_scroll, 9);
+
+        // Reaching the bottom again follows new lines
+        app.page_down();
+        assert!(!app.log_follow);
+        app.next_item();
+        assert!(app.log_follow);
+        assert_eq!(app.log_scroll, 20);
+
+        app.toggle_log_follow();
+        assert!(!app.log_follow);
+        assert_eq!(app.log_scroll, 20);
+
+        // After a resize shrinks the bottom, scrolling up starts from there
+        app.log_scroll = 20;
+        app.log_bottom.set(12);
+        app.previous_item();
+        assert_eq!(app.log_scroll, 11);
+
+        // Jumping to the end lands on the bottom row, not the last line
+        app.page_up();
+        app.scroll_to_bottom();
+        assert!(app.log_follow);
+        assert_eq!(app.log_scroll, 12);
+    }
+
+    #[test]
+    fn test_prompt_json_object() {
+        let result: GetPromptResult = serde_json::from_value(json!({
+            "messages": [
+                {"role": "user", "content": {"type": "text", "text": "{\"q\": \"plain\"}"}},
+                {"role": "assistant", "content": {
+                    "type": "text",
+                    "text": "Try this:\n```json\n{\"q\": \"rust\", \"limit\": 5}\n```\n"
+                }}
+            ]
+        }))
+        .unwrap();
+        // Fenced blocks win over a message that is JSON as a whole
+        let object = prompt_json_object(&result).unwrap();
+        assert_eq!(Value::Object(object), json!({"q": "rust", "limit": 5}));
+
+        let result: GetPromptResult = serde_json::from_value(json!({
+            "messages": [{"role": "user", "content": {"type": "text", "text": "no json here"}}]
+        }))
+        .unwrap();
+        assert!(prompt_json_object(&result).is_none());
+    }
+
+    #[test]
+    fn test_match_tool_arguments() {
+        let tools: Vec<Tool> = serde_json::from_value(json!([
+            {"name": "list", "inputSchema": {"type": "object"}},
+            {"name": "search", "inputSchema": {
+                "type": "object",
+                "properties": {"q": {"type": "string"}, "limit": {"type": "integer"}}
+            }}
+        ]))
+        .unwrap();
+        let object = |value: Value| value.as_object().unwrap().clone();
+
+        let (index, arguments) =
+            match_tool_arguments(&tools, 0, object(json!({"q": "rust"}))).unwrap();
+        assert_eq!((index, Value::Object(arguments)), (1, json!({"q": "rust"})));
+
+        let (index, arguments) =
+            match_tool_arguments(&tools, 1, object(json!({"name": "list", "arguments": {}})))
+                .unwrap();
+        assert_eq!((index, Value::Object(arguments)), (0, json!({})));
+
+        assert!(match_tool_arguments(&tools, 0, object(json!({"other": 1}))).is_none());
+    }
+
+    #[test]
+    fn test_redact() {
+        let patterns = [
+            Regex::new(r"ghp_[A-Za-z0-9]+").unwrap(),
+            Regex::new(r#""password":\s*"[^"]*""#).unwrap(),
+        ];
+        assert_eq!(
+            redact(
+                r#"token ghp_abc123, {"password": "hunter2"}"#.to_string(),
+                &patterns
+            ),
+            // The whole match is masked
+            r#"token ****, {****}"#
+        );
+        assert_eq!(
+            redact("nothing secret".to_string(), &patterns),
+            "nothing secret"
+        );
+    }
+
+    #[test]
+    fn test_redact_prompt_results_and_tool_errors() {
+        let mut app = App::new(false);
+        app.redact_patterns = vec![Regex::new(r"ghp_[A-Za-z0-9]+").unwrap()];
+
+        let result: GetPromptResult = serde_json::from_value(json!({
+            "messages": [{"role": "user", "content": {"type": "text", "text": "use ghp_abc123"}}]
+        }))
+        .unwrap();
+        app.apply_task_outcome(TaskOutcome::PromptGet {
+            prompt_name: "setup".to_string(),
+            result: Ok(result),
+        });
+        let detail = app.detail_view.take().unwrap().text();
+        assert!(detail.contains("use ****"));
+        assert!(!detail.contains("ghp_"));
+
+        app.apply_task_outcome(TaskOutcome::ToolCall {
+            tool_name: "missing".to_string(),
+            result: Err(anyhow::anyhow!("bad token ghp_abc123")),
+        });
+        assert_eq!(
+            app.error_message.as_deref(),
+            Some("Tool call failed: bad token ****")
+        );
+
+        app.apply_task_outcome(TaskOutcome::PromptGet {
+            prompt_name: "setup".to_string(),
+            result: Err(anyhow::anyhow!("bad token ghp_abc123")),
+        });
+        assert_eq!(
+            app.error_message.as_deref(),
+            Some("Prompt get failed: bad token ****")
+        );
+
+        app.apply_task_outcome(TaskOutcome::ResourceRead {
+            resource_name: "config".to_string(),
+            uri: "file:///config".to_string(),
+            result: Err(anyhow::anyhow!("bad token ghp_abc123")),
+        });
+        assert_eq!(
+            app.error_message.as_deref(),
+            Some("Failed to read resource 'config': bad token ****")
+        );
+    }
+
+    #[test]
+    fn test_format_logs() {
+        let mut app = App::new(false);
+        app.logs = vec!["starting\n".to_string()];
+        app.debug_logs = vec![LogEntry::new(Level::INFO, "mcpeek", "ready".to_string())];
+
+        app.log_export_format = LogExportFormat::Compact;
+        let compact = app.format_logs().unwrap();
+        assert!(!compact.contains('\n'));
+        assert_eq!(
+            serde_json::from_str::<Value>(&compact).unwrap()["server_logs"],
+            json!(["starting\n"])
+        );
+
+        app.log_export_format = LogExportFormat::Jsonl;
+        let lines: Vec<Value> = app
+            .format_logs()
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines[0], json!({"source": "server", "message": "starting"}));
+        assert_eq!(lines[1]["source"], "debug");
+        assert_eq!(lines[1]["message"], "ready");
+        assert_eq!(lines.len(), 2);
+    }
+
+    #[test]
+    fn test_selected_identifier() {
+        let mut app = App::new(false);
+        app.resources = serde_json::from_value(json!([
+            {"uri": "file:///a.txt", "name": "a"},
+            {"uri": "file:///b.txt", "name": "b"}
+        ]))
+        .unwrap();
+        app.current_tab = Tab::Resources;
+        app.selected_resource = 1;
+        assert_eq!(app.selected_identifier(), Some("file:///b.txt"));
+
+        app.current_tab = Tab::Tools;
+        assert_eq!(app.selected_identifier(), None);
+    }
+
+    #[test]
+    fn test_detail_view_sections() {
+        let section = |title: &str, body: &str| DetailPart::Section {
+            title: title.to_string(),
+            body: body.to_string(),
+        };
+        let mut detail = DetailView::sectioned(vec![
+            DetailPart::Text("Content:\n".to_string()),
+            section("one", "a\nb"),
+            section("two", "c\n"),
+            DetailPart::Text("end".to_string()),
+        ]);
+        assert_eq!(detail.text(), "Content:\none\na\nb\n\ntwo\nc\n\nend");
+        assert_eq!(detail.section_rows(), [1, 5]);
+
+        // Collapsing from inside the first section's body
+        assert_eq!(detail.toggle_section(3), Some(1));
+        let rows: Vec<&str> = detail.rows(0, 10).iter().map(|row| row.text).collect();
+        assert_eq!(rows, ["Content:", "one", "two", "c", "", "end"]);
+        assert_eq!(detail.rows(1, 2)[0].section, Some(true));
+        assert_eq!(detail.section_rows(), [1, 2]);
+
+        detail.toggle_all_sections();
+        assert_eq!(detail.line_count(), 4);
+        detail.toggle_all_sections();
+        assert_eq!(detail.line_count(), 9);
+    }
+
+    #[test]
+    fn test_tool_result_sections() {
+        let result: CallToolResult = serde_json::from_value(json!({
+            "content": [
+                {"type": "text", "text": "first\nline"},
+                {"type": "text", "text": "second"}
+            ]
+        }))
+        .unwrap();
+        let parts = tool_result_parts("echo", &result, None);
+        assert_eq!(parts.len(), 3);
+        assert_eq!(
+            parts[1],
+            DetailPart::Section {
+                title: "[1/2] text (2 lines)".to_string(),
+                body: "first\nline".to_string(),
+            }
+        );
+
+        // A single item isn't split into sections
+        let result: CallToolResult =
+            serde_json::from_value(json!({"content": [{"type": "text", "text": "only"}]})).unwrap();
+        assert_eq!(
+            tool_result_parts("echo", &result, None),
+            [DetailPart::Text(
+                "Tool Call Result: echo\n\nStatus: SUCCESS\n\nContent:\nonly".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_describe_root() {
+        let mut root = Root {
+            uri: "file:///work/src".to_string(),
+            name: Some("src".to_string()),
+        };
+        assert_eq!(describe_root(&root), "src (file:///work/src)");
+        root.name = None;
+        assert_eq!(describe_root(&root), "file:///work/src");
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_secs(0)), "0s");
+        assert_eq!(format_duration(Duration::from_secs(59)), "59s");
+        assert_eq!(format_duration(Duration::from_secs(150)), "2m");
+        assert_eq!(format_duration(Duration::from_secs(3900)), "1h 5m");
+        assert_eq!(format_age(Duration::from_secs(150)), "2m ago");
+    }
+
+    #[test]
+    fn test_problem_level() {
+        assert_eq!(problem_level("ERROR: db down\n"), Some(Level::ERROR));
+        assert_eq!(problem_level("[Warning] slow query"), Some(Level::WARN));
+        assert_eq!(problem_level("listening on :8080"), None);
+    }
+
+    #[test]
+    fn test_collapse_repeats() {
+        let lines: Vec<String> = ["a\n", "b\n", "b\n", "b\n", "a\n", "a\n"]
+            .iter()
+            .map(|l| l.to_string())
+            .collect();
+        assert_eq!(
+            collapse_repeats(&lines),
+            vec![("a\n", 1), ("b\n", 3), ("a\n", 2)]
+        );
+        assert!(collapse_repeats(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_coerce_number_negative_decimal() {
+        assert_eq!(coerce_input_value("n", "number", "-3.5"), Ok(json!(-3.5)));
+        assert_eq!(coerce_input_value("n", "number", "-7"), Ok(json!(-7)));
+    }
+
+    #[test]
+    fn test_coerce_number_scientific() {
+        assert_eq!(coerce_input_value("n", "number", "1e10"), Ok(json!(1e10)));
+        assert_eq!(
+            coerce_input_value("n", "number", "2.5E-3"),
+            Ok(json!(0.0025))
+        );
+    }
+
+    #[test]
+    fn test_coerce_number_rejects_non_finite() {
+        for input in ["NaN", "inf", "-Infinity", "1e400"] {
+            assert_eq!(
+                coerce_input_value("n", "number", input),
+                Err("'n' must be a finite number".to_string()),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_coerce_integer() {
+        assert_eq!(coerce_input_value("n", "integer", "-3"), Ok(json!(-3)));
+        assert_eq!(coerce_input_value("n", "integer", "1e3"), Ok(json!(1000)));
+        assert_eq!(coerce_input_value("n", "integer", "2.0"), Ok(json!(2)));
+        for input in ["3.5", "NaN", "1e30", "abc"] {
+            assert_eq!(
+                coerce_input_value("n", "integer", input),
+                Err("'n' must be an integer".to_string()),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_input_schema_unions() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "id": {"anyOf": [{"type": "string"}, {"type": "integer"}]},
+                "limit": {"oneOf": [{"type": "integer"}, {"type": "null"}]},
+                "tags": {"type": ["array", "null"]},
+                "mode": {"anyOf": [{"const": "fast"}, {"const": "slow"}]},
+                "name": {}
+            }
+        });
+        let types: HashMap<String, String> = parse_input_schema(&schema)
+            .into_iter()
+            .map(|f| (f.name, f.field_type))
+            .collect();
+
+        assert_eq!(types["id"], "string | integer");
+        assert_eq!(types["limit"], "integer");
+        assert_eq!(types["tags"], "array");
+        assert_eq!(types["mode"], "any");
+        assert_eq!(types["name"], "string");
+    }
+
+    #[test]
+    fn test_coerce_union() {
+        assert_eq!(
+            coerce_input_value("id", "string | integer", "42"),
+            Ok(json!(42))
+        );
+        assert_eq!(
+            coerce_input_value("id", "string | integer", "abc"),
+            Ok(json!("abc"))
+        );
+        assert_eq!(
+            coerce_input_value("v", "number | boolean", "true"),
+            Ok(json!(true))
+        );
+        assert_eq!(
+            coerce_input_value("v", "number | boolean", "abc"),
+            Err("'v' must be one of: number, boolean".to_string())
+        );
+        assert_eq!(coerce_input_value("m", "any", "[1]"), Ok(json!([1])));
+        assert_eq!(coerce_input_value("m", "any", "fast"), Ok(json!("fast")));
+    }
+
+    #[test]
+    fn test_coerce_number_rejects_empty_and_text() {
+        for input in ["", "abc", "1,5"] {
+            assert_eq!(
+                coerce_input_value("n", "number", input),
+                Err("'n' must be a number".to_string()),
+                "{}",
+                input
+            );
+        }
+    }
+}
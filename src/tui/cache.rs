@@ -0,0 +1,151 @@
+use crate::mcp::protocol::{CallToolResult, ResourceContents};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+/// Number of results kept by `--cache`.
+pub const CACHE_CAPACITY: usize = 16;
+
+/// A request whose result may be served from the cache.
+#[derive(Debug, Clone)]
+pub enum CachedRequest {
+    ToolCall {
+        tool_name: String,
+        arguments: Option<HashMap<String, Value>>,
+    },
+    ResourceRead {
+        resource_name: String,
+        uri: String,
+    },
+}
+
+impl CachedRequest {
+    /// Cache key built from the method and its arguments. Arguments are
+    /// sorted so the same call always gives the same key.
+    pub fn key(&self) -> String {
+        match self {
+            CachedRequest::ToolCall {
+                tool_name,
+                arguments,
+            } => {
+                let sorted: Option<BTreeMap<&String, &Value>> =
+                    arguments.as_ref().map(|args| args.iter().collect());
+                format!(
+                    "tools/call {} {}",
+                    tool_name,
+                    serde_json::to_string(&sorted).unwrap_or_default()
+                )
+            }
+            CachedRequest::ResourceRead { uri, .. } => format!("resources/read {}", uri),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum CachedResult {
+    ToolCall(CallToolResult),
+    ResourceRead(Vec<ResourceContents>),
+}
+
+/// Least recently used cache of results for read-only requests.
+pub struct ResultCache {
+    capacity: usize,
+    // Most recently used first
+    entries: VecDeque<(String, CachedResult)>,
+}
+
+impl ResultCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: VecDeque::new(),
+        }
+    }
+
+    /// Look up a result, marking it as most recently used.
+    pub fn get(&mut self, key: &str) -> Option<CachedResult> {
+        let index = self.entries.iter().position(|(k, _)| k == key)?;
+        let entry = self.entries.remove(index)?;
+        let result = entry.1.clone();
+        self.entries.push_front(entry);
+        Some(result)
+    }
+
+    /// Store a result, evicting the least recently used one when full.
+    pub fn insert(&mut self, key: String, result: CachedResult) {
+        self.entries.retain(|(k, _)| *k != key);
+        self.entries.push_front((key, result));
+        self.entries.truncate(self.capacity);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn read_result(text: &str) -> CachedResult {
+        CachedResult::ResourceRead(vec![ResourceContents::Text {
+            uri: "file:///a.txt".to_string(),
+            text: text.to_string(),
+            mime_type: None,
+        }])
+    }
+
+    fn text(result: Option<CachedResult>) -> Option<String> {
+        match result? {
+            CachedResult::ResourceRead(contents) => match &contents[0] {
+                ResourceContents::Text { text, .. } => Some(text.clone()),
+                _ => None,
+            },
+            CachedResult::ToolCall(_) => None,
+        }
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let mut cache = ResultCache::new(2);
+        cache.insert("a".to_string(), read_result("1"));
+        cache.insert("b".to_string(), read_result("2"));
+
+        // Looking up "a" makes "b" the least recently used
+        assert_eq!(text(cache.get("a")).as_deref(), Some("1"));
+        cache.insert("c".to_string(), read_result("3"));
+        assert!(cache.get("b").is_none());
+        assert_eq!(text(cache.get("a")).as_deref(), Some("1"));
+        assert_eq!(text(cache.get("c")).as_deref(), Some("3"));
+
+        // Storing an existing key replaces it rather than taking a slot
+        cache.insert("c".to_string(), read_result("4"));
+        assert_eq!(text(cache.get("c")).as_deref(), Some("4"));
+        assert_eq!(text(cache.get("a")).as_deref(), Some("1"));
+    }
+
+    #[test]
+    fn test_key_ignores_argument_order() {
+        let call = |pairs: &[(&str, Value)]| CachedRequest::ToolCall {
+            tool_name: "search".to_string(),
+            arguments: Some(
+                pairs
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.clone()))
+                    .collect(),
+            ),
+        };
+        let key = call(&[("q", json!("rust")), ("limit", json!(5))]).key();
+        assert_eq!(
+            key,
+            call(&[("limit", json!(5)), ("q", json!("rust"))]).key()
+        );
+        assert_eq!(key, r#"tools/call search {"limit":5,"q":"rust"}"#);
+        assert_ne!(
+            key,
+            call(&[("q", json!("rust")), ("limit", json!(6))]).key()
+        );
+
+        let read = CachedRequest::ResourceRead {
+            resource_name: "A".to_string(),
+            uri: "file:///a.txt".to_string(),
+        };
+        assert_eq!(read.key(), "resources/read file:///a.txt");
+    }
+}
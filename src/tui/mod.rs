@@ -1,4 +1,6 @@
 pub mod app;
+pub mod cache;
+pub mod settings;
 pub mod ui;
 
 pub use app::{App, Tab};
@@ -0,0 +1,82 @@
+use super::app::ToolSort;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// View preferences kept across runs in the state file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    // Show only names in the tools, prompts and resources lists
+    pub dense_lists: bool,
+    // Show tools as a table with a column per piece of metadata
+    pub tool_table: bool,
+    // None keeps the order the server listed the tools in
+    pub tool_sort: Option<ToolSort>,
+    // Show binary resource contents as a hex dump
+    pub hex_dump: bool,
+    // Only show debug log entries with exactly this request id
+    pub debug_log_filter: Option<String>,
+    // Wrap the detail view and logs in a centered column this wide
+    pub wrap_width: Option<u16>,
+}
+
+impl Settings {
+    /// `$XDG_CONFIG_HOME/mcpeek/state.json`, or `~/.config/mcpeek/state.json`.
+    pub fn default_path() -> Option<PathBuf> {
+        let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME")
+                    .filter(|dir| !dir.is_empty())
+                    .map(|home| PathBuf::from(home).join(".config"))
+            })?;
+        Some(config_dir.join("mcpeek").join("state.json"))
+    }
+
+    /// Read saved settings. A missing file gives the defaults.
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        };
+        serde_json::from_str(&text)
+            .with_context(|| format!("Invalid state file {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json).with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_fields_use_defaults() {
+        let settings: Settings =
+            serde_json::from_str(r#"{"dense_lists": true, "tool_sort": "required"}"#).unwrap();
+        assert_eq!(
+            settings,
+            Settings {
+                dense_lists: true,
+                tool_sort: Some(ToolSort::Required),
+                ..Settings::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_load_missing_file_gives_defaults() {
+        let path = std::env::temp_dir().join("mcpeek-test-no-such-dir/state.json");
+        assert_eq!(Settings::load(&path).unwrap(), Settings::default());
+    }
+}
@@ -1,11 +1,20 @@
-use super::app::{App, Tab};
+use super::app::{
+    describe_root, format_age, problem_level, roots_summary, App, DetailView, InputPopup, Tab,
+    TextPrompt, ToolConfirmation, ToolSort,
+};
+use crate::mcp::protocol::{duplicate_names, Tool};
 use ratatui::{
-    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    buffer::Buffer,
+    layout::{Alignment, Constraint, Direction, Flex, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span, Text},
-    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Tabs, Wrap},
+    widgets::{
+        Block, Borders, Cell, Clear, List, ListItem, ListState, Padding, Paragraph, Row, Table,
+        TableState, Tabs, Wrap,
+    },
     Frame,
 };
+use tracing::Level;
 
 pub fn render_ui(f: &mut Frame, app: &App) {
     let chunks = Layout::default()
@@ -36,36 +45,207 @@ pub fn render_ui(f: &mut Frame, app: &App) {
     if app.prompt_input_mode {
         render_prompt_input_form(f, app);
     }
+
+    // Render single-line text prompt as overlay
+    if let Some(prompt) = &app.text_prompt {
+        render_text_prompt(f, prompt);
+    }
+
+    // Render destructive tool confirmation on top of the tool form
+    if let Some(confirmation) = &app.tool_confirmation {
+        render_tool_confirmation(f, confirmation);
+    }
+
+    if app.show_help {
+        render_help_overlay(f, app);
+    }
+
+    if app.no_color {
+        strip_colors(f.buffer_mut());
+    }
 }
 
-fn render_tabs(f: &mut Frame, app: &App, area: Rect) {
-    let mut tab_titles = vec![
-        Tab::Tools.as_str(),
-        Tab::Prompts.as_str(),
-        Tab::Resources.as_str(),
-        Tab::ServerInfo.as_str(),
-        Tab::ServerLogs.as_str(),
-    ];
+/// Replace the colors in a rendered frame with modifiers, for terminals
+/// without color. Highlight backgrounds become reverse video, and red,
+/// yellow and magenta text (errors, warnings and prompts) becomes bold.
+fn strip_colors(buf: &mut Buffer) {
+    for cell in buf.content.iter_mut() {
+        match cell.bg {
+            Color::Reset | Color::Black => {}
+            _ => cell.modifier.insert(Modifier::REVERSED),
+        }
+        if matches!(cell.fg, Color::Red | Color::Yellow | Color::Magenta) {
+            cell.modifier.insert(Modifier::BOLD);
+        }
+        cell.fg = Color::Reset;
+        cell.bg = Color::Reset;
+    }
+}
 
-    if app.debug_mode {
-        tab_titles.push(Tab::DebugLogs.as_str());
+/// Every keybinding, grouped by the context it applies in.
+const KEYBINDINGS: &[(&str, &[(&str, &str)])] = &[
+    (
+        "Navigation",
+        &[
+            ("Tab / →", "Next tab"),
+            ("Shift+Tab / ←", "Previous tab"),
+            ("↑/↓, PgUp/PgDn", "Move selection or scroll"),
+            ("Enter", "Show details"),
+            ("R", "Refresh current tab"),
+            ("?", "Show or hide this help"),
+            ("Q", "Quit"),
+        ],
+    ),
+    (
+        "Tabs",
+        &[
+            ("C", "Call tool / get prompt / read resource"),
+            ("U", "Read a resource by URI (Resources)"),
+            (
+                "Y",
+                "Copy the selected tool or prompt name, or resource URI",
+            ),
+            ("D", "Toggle dense lists showing names only"),
+            (
+                "T",
+                "Toggle the tools table (Tools) or following new lines (Logs)",
+            ),
+            ("O", "Sort the tools table by the next column (Tools)"),
+            ("M", "Toggle most recently called tools first (Tools)"),
+            ("I", "Re-run the initialize handshake (Server Info)"),
+            ("S", "Save server info (Server Info) or logs (Logs)"),
+            ("E", "Jump to the end of the logs"),
+            (
+                "N / P",
+                "Jump to the next / previous error or warning (Logs)",
+            ),
+            ("F", "Filter debug logs by request ID"),
+        ],
+    ),
+    (
+        "Detail view",
+        &[
+            ("Esc", "Close"),
+            ("C", "Run the selected item again"),
+            ("X", "Toggle hex dump of binary contents"),
+            ("F", "View a truncated result in full"),
+            ("W", "Save the result to a file"),
+            ("R", "Re-fetch a cached result"),
+            ("J", "Switch between an item's summary and its raw JSON"),
+            (
+                "N / P",
+                "Jump to the next / previous content item of a tool result",
+            ),
+            ("Z", "Collapse or expand the content item at the top"),
+            ("V", "Collapse or expand every content item"),
+            ("L", "Toggle the wrap width cap (also in the Logs tabs)"),
+            (
+                "A",
+                "Fill in a tool call from the JSON object in a prompt result",
+            ),
+        ],
+    ),
+    (
+        "Input forms",
+        &[
+            ("Tab / Shift+Tab", "Next / previous field"),
+            ("←/→, Home/End", "Move the cursor"),
+            ("Ctrl+W / Ctrl+U", "Delete word / clear field"),
+            ("Ctrl+J", "Switch between form and raw JSON (tools)"),
+            ("Alt+Enter", "New line in raw JSON"),
+            ("Enter", "Submit"),
+            ("Esc", "Cancel"),
+        ],
+    ),
+    ("While a request runs", &[("Esc", "Cancel the request")]),
+];
+
+fn render_help_overlay(f: &mut Frame, app: &App) {
+    let bold = Style::default().add_modifier(Modifier::BOLD);
+    let key_width = KEYBINDINGS
+        .iter()
+        .flat_map(|(_, keys)| keys.iter())
+        .map(|(key, _)| key.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    let mut lines = vec![
+        Line::from(Span::styled(
+            format!("mcpeek v{}", env!("CARGO_PKG_VERSION")),
+            bold,
+        )),
+        Line::from(format!("Connected to: {}", app.connection_target)),
+    ];
+    for (context, keys) in KEYBINDINGS {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(*context, bold)));
+        for (key, action) in *keys {
+            lines.push(Line::from(vec![
+                Span::styled(
+                    format!("  {:width$}", key, width = key_width),
+                    Style::default().fg(Color::Cyan),
+                ),
+                Span::raw(format!("  {}", action)),
+            ]));
+        }
     }
 
-    let selected_index = match app.current_tab {
-        Tab::Tools => 0,
-        Tab::Prompts => 1,
-        Tab::Resources => 2,
-        Tab::ServerInfo => 3,
-        Tab::ServerLogs => 4,
-        Tab::DebugLogs => 5,
+    let area = f.area();
+    let popup_width = area.width.saturating_sub(4).min(70);
+    let popup_height = (lines.len() as u16 + 2).min(area.height);
+    let popup_area = Rect {
+        x: (area.width.saturating_sub(popup_width)) / 2,
+        y: (area.height.saturating_sub(popup_height)) / 2,
+        width: popup_width,
+        height: popup_height,
     };
 
-    let tabs = Tabs::new(tab_titles)
+    f.render_widget(Clear, popup_area);
+
+    let max_scroll = (lines.len() + 2).saturating_sub(popup_height as usize);
+    let scroll = app.help_scroll.min(max_scroll) as u16;
+    let paragraph = Paragraph::new(lines)
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title("mcpeek - An MCP Inspector"),
+                .title("Help (↑/↓: Scroll | Any other key: Close)")
+                .padding(Padding::horizontal(1))
+                .style(Style::default().bg(Color::Black)),
         )
+        .scroll((scroll, 0));
+
+    f.render_widget(paragraph, popup_area);
+}
+
+fn render_tabs(f: &mut Frame, app: &App, area: Rect) {
+    let tab_titles: Vec<&str> = app.tabs.iter().map(|tab| tab.as_str()).collect();
+    let selected_index = app
+        .tabs
+        .iter()
+        .position(|tab| *tab == app.current_tab)
+        .unwrap_or(0);
+
+    let mut block = Block::default()
+        .borders(Borders::ALL)
+        .title("mcpeek - An MCP Inspector");
+    if app.disconnected {
+        block = block.title(
+            Line::from(" disconnected (has the server exited?) ")
+                .style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))
+                .right_aligned(),
+        );
+    } else if let Some(loaded) = app.last_loaded.get(&app.current_tab) {
+        block = block.title(
+            Line::from(format!(
+                " refreshed {} (R: Refresh) ",
+                format_age(loaded.elapsed())
+            ))
+            .right_aligned(),
+        );
+    }
+
+    let tabs = Tabs::new(tab_titles)
+        .block(block)
         .select(selected_index)
         .style(Style::default().fg(Color::White))
         .highlight_style(
@@ -105,53 +285,225 @@ fn render_content(f: &mut Frame, app: &App, area: Rect) {
     }
 }
 
+/// List block title, with a warning about any names the server repeats.
+fn list_title(title: String, duplicates: Vec<&str>) -> Line<'static> {
+    let mut spans = vec![Span::raw(title)];
+    if !duplicates.is_empty() {
+        spans.push(Span::styled(
+            format!(" ⚠ duplicates: {}", duplicates.join(", ")),
+            Style::default().fg(Color::Yellow),
+        ));
+    }
+    Line::from(spans)
+}
+
+/// What to show for an empty list, telling a server without the capability
+/// apart from one that just has nothing to list.
+fn empty_list_message(app: &App, tab: Tab, kind: &str) -> String {
+    if app.lacks_capability(tab) {
+        format!("Server does not support {}", kind)
+    } else if tab == Tab::Tools && app.unselected_tools > 0 {
+        format!("No tools match --select ({} hidden)", app.unselected_tools)
+    } else {
+        format!("No {} available", kind)
+    }
+}
+
+/// Number of tools shown, and how many `--select` left out.
+fn tool_count(app: &App) -> String {
+    if app.unselected_tools > 0 {
+        format!(
+            "{} of {}, --select",
+            app.tools.len(),
+            app.tools.len() + app.unselected_tools
+        )
+    } else {
+        app.tools.len().to_string()
+    }
+}
+
 fn render_tools(f: &mut Frame, app: &App, area: Rect) {
     if app.tools.is_empty() {
-        let empty = Paragraph::new("No tools available")
+        let empty = Paragraph::new(empty_list_message(app, Tab::Tools, "tools"))
             .block(Block::default().borders(Borders::ALL).title("Tools"))
             .alignment(Alignment::Center);
         f.render_widget(empty, area);
         return;
     }
 
+    if app.settings.tool_table {
+        render_tools_table(f, app, area);
+        return;
+    }
+
     let items: Vec<ListItem> = app
         .tools
         .iter()
         .map(|tool| {
-            let content = vec![Line::from(vec![
-                Span::styled(
-                    &tool.name,
+            let mut spans = vec![Span::styled(
+                &tool.name,
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            )];
+            if app.settings.dense_lists {
+                spans.extend(tool_badges(tool));
+                return ListItem::new(Line::from(spans));
+            }
+            if let Some(title) = &tool.title {
+                spans.push(Span::styled(
+                    format!(" ({})", title),
+                    Style::default().fg(Color::White),
+                ));
+            }
+            spans.extend(tool_badges(tool));
+            spans.push(Span::raw(" - "));
+            spans.push(Span::raw(
+                tool.description.as_deref().unwrap_or("No description"),
+            ));
+            ListItem::new(vec![Line::from(spans)])
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(list_title(
+            if app.settings.tool_sort == Some(ToolSort::Recent) {
+                format!("Tools ({}, recently used first)", tool_count(app))
+            } else {
+                format!("Tools ({})", tool_count(app))
+            },
+            duplicate_names(app.tools.iter().map(|t| t.name.as_str())),
+        )))
+        .highlight_style(
+            Style::default()
+                .bg(Color::DarkGray)
+                .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol("> ");
+
+    let mut state = ListState::default()
+        .with_selected(Some(app.selected_tool))
+        .with_offset(app.tool_list_offset.get());
+    f.render_stateful_widget(list, area, &mut state);
+    app.tool_list_offset.set(state.offset());
+}
+
+/// Tools as a table of name, annotations, required parameter count and
+/// description, sorted by `app.settings.tool_sort`.
+fn render_tools_table(f: &mut Frame, app: &App, area: Rect) {
+    let columns = [
+        ("Name", Some(ToolSort::Name)),
+        ("Annotations", Some(ToolSort::Annotations)),
+        ("Required", Some(ToolSort::Required)),
+        ("Description", Some(ToolSort::Description)),
+    ];
+    let header = Row::new(columns.map(|(label, sort)| {
+        let label = if sort == app.settings.tool_sort && sort.is_some() {
+            format!("{} ▼", label)
+        } else {
+            label.to_string()
+        };
+        Cell::from(label)
+    }))
+    .style(
+        Style::default()
+            .fg(Color::Yellow)
+            .add_modifier(Modifier::BOLD),
+    );
+
+    let rows: Vec<Row> = app
+        .tools
+        .iter()
+        .map(|tool| {
+            Row::new(vec![
+                Cell::from(Span::styled(
+                    tool.name.clone(),
                     Style::default()
                         .fg(Color::Cyan)
                         .add_modifier(Modifier::BOLD),
+                )),
+                Cell::from(Line::from(tool_badges(tool))),
+                Cell::from(app.required_param_count(tool).to_string()),
+                Cell::from(
+                    tool.description
+                        .as_deref()
+                        .unwrap_or("No description")
+                        .lines()
+                        .next()
+                        .unwrap_or_default()
+                        .to_string(),
                 ),
-                Span::raw(" - "),
-                Span::raw(tool.description.as_deref().unwrap_or("No description")),
-            ])];
-            ListItem::new(content)
+            ])
         })
         .collect();
 
-    let list = List::new(items)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title(format!("Tools ({})", app.tools.len())),
-        )
-        .highlight_style(
+    let name_width = app
+        .tools
+        .iter()
+        .map(|t| t.name.chars().count())
+        .max()
+        .unwrap_or(0)
+        .clamp(4, 32) as u16;
+    let widths = [
+        Constraint::Length(name_width),
+        // Room for all three badges
+        Constraint::Length(16),
+        Constraint::Length(8),
+        Constraint::Fill(1),
+    ];
+
+    let sort = match app.settings.tool_sort {
+        Some(ToolSort::Name) => "name",
+        Some(ToolSort::Annotations) => "annotations",
+        Some(ToolSort::Required) => "required params",
+        Some(ToolSort::Description) => "description",
+        Some(ToolSort::Recent) => "recently used",
+        None => "server order",
+    };
+    let table = Table::new(rows, widths)
+        .header(header)
+        .block(Block::default().borders(Borders::ALL).title(list_title(
+            format!("Tools ({}, by {})", tool_count(app), sort),
+            duplicate_names(app.tools.iter().map(|t| t.name.as_str())),
+        )))
+        .row_highlight_style(
             Style::default()
                 .bg(Color::DarkGray)
                 .add_modifier(Modifier::BOLD),
         )
         .highlight_symbol("> ");
 
-    let mut state = ListState::default().with_selected(Some(app.selected_tool));
-    f.render_stateful_widget(list, area, &mut state);
+    let mut state = TableState::default()
+        .with_selected(Some(app.selected_tool))
+        .with_offset(app.tool_list_offset.get());
+    f.render_stateful_widget(table, area, &mut state);
+    app.tool_list_offset.set(state.offset());
+}
+
+/// Small markers for a tool's behaviour annotations: read-only, destructive
+/// and idempotent. Tools without annotations get none.
+fn tool_badges(tool: &Tool) -> Vec<Span<'static>> {
+    let badges = [
+        ("readOnlyHint", "RO", Color::Green),
+        ("destructiveHint", "!", Color::Red),
+        ("idempotentHint", "IDEM", Color::Blue),
+    ];
+
+    badges
+        .into_iter()
+        .filter(|(hint, _, _)| tool.hint(hint) == Some(true))
+        .map(|(_, label, color)| {
+            Span::styled(
+                format!(" [{}]", label),
+                Style::default().fg(color).add_modifier(Modifier::BOLD),
+            )
+        })
+        .collect()
 }
 
 fn render_prompts(f: &mut Frame, app: &App, area: Rect) {
     if app.prompts.is_empty() {
-        let empty = Paragraph::new("No prompts available")
+        let empty = Paragraph::new(empty_list_message(app, Tab::Prompts, "prompts"))
             .block(Block::default().borders(Borders::ALL).title("Prompts"))
             .alignment(Alignment::Center);
         f.render_widget(empty, area);
@@ -162,14 +514,18 @@ fn render_prompts(f: &mut Frame, app: &App, area: Rect) {
         .prompts
         .iter()
         .map(|prompt| {
+            let name = Span::styled(
+                &prompt.name,
+                Style::default()
+                    .fg(Color::Green)
+                    .add_modifier(Modifier::BOLD),
+            );
+            if app.settings.dense_lists {
+                return ListItem::new(Line::from(name));
+            }
             let args_count = prompt.arguments.as_ref().map(|a| a.len()).unwrap_or(0);
             let content = vec![Line::from(vec![
-                Span::styled(
-                    &prompt.name,
-                    Style::default()
-                        .fg(Color::Green)
-                        .add_modifier(Modifier::BOLD),
-                ),
+                name,
                 Span::raw(format!(" ({} args) - ", args_count)),
                 Span::raw(prompt.description.as_deref().unwrap_or("No description")),
             ])];
@@ -178,11 +534,10 @@ fn render_prompts(f: &mut Frame, app: &App, area: Rect) {
         .collect();
 
     let list = List::new(items)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title(format!("Prompts ({})", app.prompts.len())),
-        )
+        .block(Block::default().borders(Borders::ALL).title(list_title(
+            format!("Prompts ({})", app.prompts.len()),
+            duplicate_names(app.prompts.iter().map(|p| p.name.as_str())),
+        )))
         .highlight_style(
             Style::default()
                 .bg(Color::DarkGray)
@@ -190,13 +545,16 @@ fn render_prompts(f: &mut Frame, app: &App, area: Rect) {
         )
         .highlight_symbol("> ");
 
-    let mut state = ListState::default().with_selected(Some(app.selected_prompt));
+    let mut state = ListState::default()
+        .with_selected(Some(app.selected_prompt))
+        .with_offset(app.prompt_list_offset.get());
     f.render_stateful_widget(list, area, &mut state);
+    app.prompt_list_offset.set(state.offset());
 }
 
 fn render_resources(f: &mut Frame, app: &App, area: Rect) {
     if app.resources.is_empty() {
-        let empty = Paragraph::new("No resources available")
+        let empty = Paragraph::new(empty_list_message(app, Tab::Resources, "resources"))
             .block(Block::default().borders(Borders::ALL).title("Resources"))
             .alignment(Alignment::Center);
         f.render_widget(empty, area);
@@ -207,13 +565,17 @@ fn render_resources(f: &mut Frame, app: &App, area: Rect) {
         .resources
         .iter()
         .map(|resource| {
+            let name = Span::styled(
+                &resource.name,
+                Style::default()
+                    .fg(Color::Magenta)
+                    .add_modifier(Modifier::BOLD),
+            );
+            if app.settings.dense_lists {
+                return ListItem::new(Line::from(name));
+            }
             let content = vec![Line::from(vec![
-                Span::styled(
-                    &resource.name,
-                    Style::default()
-                        .fg(Color::Magenta)
-                        .add_modifier(Modifier::BOLD),
-                ),
+                name,
                 Span::raw(" - "),
                 Span::styled(&resource.uri, Style::default().fg(Color::Blue)),
             ])];
@@ -222,11 +584,10 @@ fn render_resources(f: &mut Frame, app: &App, area: Rect) {
         .collect();
 
     let list = List::new(items)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title(format!("Resources ({})", app.resources.len())),
-        )
+        .block(Block::default().borders(Borders::ALL).title(list_title(
+            format!("Resources ({})", app.resources.len()),
+            duplicate_names(app.resources.iter().map(|r| r.uri.as_str())),
+        )))
         .highlight_style(
             Style::default()
                 .bg(Color::DarkGray)
@@ -234,8 +595,11 @@ fn render_resources(f: &mut Frame, app: &App, area: Rect) {
         )
         .highlight_symbol("> ");
 
-    let mut state = ListState::default().with_selected(Some(app.selected_resource));
+    let mut state = ListState::default()
+        .with_selected(Some(app.selected_resource))
+        .with_offset(app.resource_list_offset.get());
     f.render_stateful_widget(list, area, &mut state);
+    app.resource_list_offset.set(state.offset());
 }
 
 fn render_server_info(f: &mut Frame, app: &App, area: Rect) {
@@ -259,6 +623,11 @@ fn render_server_info(f: &mut Frame, app: &App, area: Rect) {
                 Span::raw(&info.protocol_version),
             ]),
             Line::from(""),
+            Line::from(vec![
+                Span::styled("Connected: ", Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(format_age(app.connected_at.elapsed())),
+            ]),
+            Line::from(""),
             Line::from(Span::styled(
                 "Capabilities:",
                 Style::default().add_modifier(Modifier::BOLD),
@@ -315,6 +684,56 @@ fn render_server_info(f: &mut Frame, app: &App, area: Rect) {
             lines.push(Line::from("  Logging: No"));
         }
 
+        let experimental = caps.experimental_names();
+        if experimental.is_empty() {
+            lines.push(Line::from("  Experimental: None"));
+        } else {
+            lines.push(Line::from(format!(
+                "  Experimental: {}",
+                experimental.join(", ")
+            )));
+        }
+
+        if let Some(params) = &app.client_params {
+            let client_caps = &params.capabilities;
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled(
+                format!(
+                    "Client Capabilities (sent by {} v{}):",
+                    params.client_info.name, params.client_info.version
+                ),
+                Style::default().add_modifier(Modifier::BOLD),
+            )));
+            lines.push(Line::from(format!(
+                "  Protocol Version: {}",
+                params.protocol_version
+            )));
+            lines.push(Line::from(format!(
+                "  Roots: {}",
+                roots_summary(client_caps.roots.as_ref())
+            )));
+            lines.push(Line::from(format!(
+                "  Sampling: {}",
+                if client_caps.sampling.is_some() {
+                    "Yes"
+                } else {
+                    "No"
+                }
+            )));
+            if !client_caps.extra.is_empty() {
+                let names: Vec<&str> = client_caps.extra.keys().map(String::as_str).collect();
+                lines.push(Line::from(format!("  Other: {}", names.join(", "))));
+            }
+            if app.roots.is_empty() {
+                lines.push(Line::from("  Configured Roots: None"));
+            } else {
+                lines.push(Line::from("  Configured Roots:"));
+                for root in &app.roots {
+                    lines.push(Line::from(format!("    - {}", describe_root(root))));
+                }
+            }
+        }
+
         if let Some(instructions) = &info.instructions {
             lines.push(Line::from(""));
             lines.push(Line::from(Span::styled(
@@ -344,17 +763,93 @@ fn render_server_info(f: &mut Frame, app: &App, area: Rect) {
     f.render_widget(paragraph, area);
 }
 
-fn render_detail(f: &mut Frame, app: &App, detail: &str, area: Rect) {
-    let paragraph = Paragraph::new(detail)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title("Detail View (↑/↓: Scroll | Esc: Close)"),
-        )
-        .wrap(Wrap { trim: false })
-        .scroll((app.detail_scroll as u16, 0));
+fn render_detail(f: &mut Frame, app: &App, detail: &DetailView, area: Rect) {
+    // Only wrap the lines that can be on screen; results can be huge
+    let rows = area.height.saturating_sub(2) as usize;
+    let start = app.detail_scroll.min(detail.line_count());
+    let resource_style = Style::default()
+        .fg(Color::Cyan)
+        .add_modifier(Modifier::UNDERLINED);
+    let section_style = Style::default().add_modifier(Modifier::BOLD);
+    let lines: Vec<Line> = detail
+        .rows(start, start + rows)
+        .into_iter()
+        .map(|row| match row.section {
+            Some(collapsed) => Line::styled(
+                format!("{} {}", if collapsed { "▸" } else { "▾" }, row.text),
+                section_style,
+            ),
+            // Make resource references stand out from the text around them
+            None if row.text.starts_with("[Resource: ")
+                || row.text.starts_with("[Binary Resource: ") =>
+            {
+                Line::styled(row.text, resource_style)
+            }
+            None => Line::from(row.text),
+        })
+        .collect();
 
-    f.render_widget(paragraph, area);
+    // Status messages (e.g. from saving) show in the title, over the content
+    let title = match &app.error_message {
+        Some(message) => format!("Detail View - {}", message),
+        None if app.cached_request.is_some() => {
+            "Detail View (cached result, R: Refresh | Esc: Close)".to_string()
+        }
+        None => "Detail View (↑/↓: Scroll | Esc: Close)".to_string(),
+    };
+    let paragraph = Paragraph::new(lines).wrap(Wrap { trim: false });
+    render_wrapped(
+        f,
+        app,
+        Block::default().borders(Borders::ALL).title(title),
+        paragraph,
+        area,
+    );
+}
+
+/// Render `paragraph` in `block`, wrapped in a centered column no wider than
+/// the `--wrap-width` cap, if there is one.
+fn render_wrapped(f: &mut Frame, app: &App, block: Block, paragraph: Paragraph, area: Rect) {
+    let inner = wrap_area(app, block.inner(area));
+    f.render_widget(block, area);
+    f.render_widget(paragraph, inner);
+}
+
+/// The part of `inner` that content is wrapped to: a centered column when
+/// a wrap width is set.
+fn wrap_area(app: &App, inner: Rect) -> Rect {
+    match app.settings.wrap_width {
+        Some(width) => {
+            let [column] = Layout::horizontal([Constraint::Max(width)])
+                .flex(Flex::Center)
+                .areas(inner);
+            column
+        }
+        None => inner,
+    }
+}
+
+/// Row each of `lines` starts on once wrapped to `area`, and the scroll
+/// offset that brings the last of them to the bottom (roughly: wrapping is
+/// by width rather than on word boundaries).
+fn wrapped_rows(lines: &[Line], area: Rect) -> (Vec<usize>, usize) {
+    let width = (area.width as usize).max(1);
+    let mut starts = Vec::with_capacity(lines.len());
+    let mut rows = 0;
+    for line in lines {
+        starts.push(rows);
+        rows += line.width().div_ceil(width).max(1);
+    }
+    (starts, rows.saturating_sub(area.height as usize))
+}
+
+/// Highlight the log line an N/P jump landed on.
+fn mark_problem(lines: &mut [Line], app: &App, tab: Tab) {
+    if let Some((marked, index)) = app.problem_mark {
+        if let Some(line) = lines.get_mut(index).filter(|_| marked == tab) {
+            line.style = line.style.add_modifier(Modifier::REVERSED);
+        }
+    }
 }
 
 fn render_logs(f: &mut Frame, app: &App, area: Rect) {
@@ -370,17 +865,45 @@ fn render_logs(f: &mut Frame, app: &App, area: Rect) {
         return;
     }
 
-    let log_text = app.logs.join("");
+    let mut log_lines: Vec<Line> = app
+        .collapsed_logs()
+        .into_iter()
+        .map(|(line, count)| {
+            let style = match problem_level(line) {
+                Some(Level::ERROR) => Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                Some(_) => Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+                None => Style::default(),
+            };
+            let line = line.trim_end_matches('\n');
+            match count {
+                1 => Line::styled(line.to_string(), style),
+                _ => Line::styled(format!("{} (x{})", line, count), style),
+            }
+        })
+        .collect();
 
-    let paragraph = Paragraph::new(log_text)
-        .block(Block::default().borders(Borders::ALL).title(format!(
-            "Server Logs ({} lines) - ↑/↓: Scroll | E: Jump to End | S: Save",
-            app.logs.len()
-        )))
+    let block = Block::default().borders(Borders::ALL).title(format!(
+        "Server Logs ({} lines{}) - ↑/↓: Scroll | N/P: Problems | E: Jump to End | T: Follow | S: Save",
+        app.logs.len(),
+        if app.log_follow { ", following" } else { "" }
+    ));
+    let (rows, bottom) = wrapped_rows(&log_lines, wrap_area(app, block.inner(area)));
+    app.log_rows.replace(rows);
+    app.log_bottom.set(bottom);
+    mark_problem(&mut log_lines, app, Tab::ServerLogs);
+    // Log scroll positions are wrapped rows, so only the renderer can tell
+    // how far they may go at the current size
+    let scroll = if app.log_follow {
+        bottom
+    } else {
+        app.log_scroll.min(bottom)
+    };
+    let paragraph = Paragraph::new(log_lines)
         .wrap(Wrap { trim: false })
-        .scroll((app.log_scroll as u16, 0));
-
-    f.render_widget(paragraph, area);
+        .scroll((scroll as u16, 0));
+    render_wrapped(f, app, block, paragraph, area);
 }
 
 fn render_debug_logs(f: &mut Frame, app: &App, area: Rect) {
@@ -393,8 +916,9 @@ fn render_debug_logs(f: &mut Frame, app: &App, area: Rect) {
     }
 
     // Format debug logs with color-coding by level
+    let entries = app.visible_debug_logs();
     let mut lines = Vec::new();
-    for entry in &app.debug_logs {
+    for entry in &entries {
         let level_style = match entry.level.as_str() {
             "ERROR" => Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
             "WARN" => Style::default().fg(Color::Yellow),
@@ -404,7 +928,7 @@ fn render_debug_logs(f: &mut Frame, app: &App, area: Rect) {
             _ => Style::default().fg(Color::White),
         };
 
-        let line = Line::from(vec![
+        let mut spans = vec![
             Span::styled(
                 format!("[{}] ", entry.timestamp),
                 Style::default().fg(Color::DarkGray),
@@ -414,50 +938,141 @@ fn render_debug_logs(f: &mut Frame, app: &App, area: Rect) {
                 format!("{}: ", entry.target),
                 Style::default().fg(Color::Blue),
             ),
-            Span::raw(&entry.message),
-        ]);
-        lines.push(line);
+        ];
+        let correlation = match (&entry.request_id, &entry.method) {
+            (Some(id), Some(method)) => Some(format!("[id={} {}] ", id, method)),
+            (Some(id), None) => Some(format!("[id={}] ", id)),
+            (None, Some(method)) => Some(format!("[{}] ", method)),
+            (None, None) => None,
+        };
+        if let Some(correlation) = correlation {
+            spans.push(Span::styled(
+                correlation,
+                Style::default().fg(Color::Magenta),
+            ));
+        }
+        if let Some(action) = &entry.action {
+            spans.push(Span::styled(
+                format!("[action={}] ", action),
+                Style::default().fg(Color::Green),
+            ));
+        }
+        spans.push(Span::raw(&entry.message));
+        lines.push(Line::from(spans));
     }
 
-    let text = Text::from(lines);
-
-    let paragraph = Paragraph::new(text)
-        .block(Block::default().borders(Borders::ALL).title(format!(
-            "Debug Logs ({} entries) - ↑/↓: Scroll | E: Jump to End | S: Save",
-            app.debug_logs.len()
-        )))
+    let filter = app
+        .settings
+        .debug_log_filter
+        .as_ref()
+        .map(|id| format!(", filtered by id={}", id))
+        .unwrap_or_default();
+
+    let block = Block::default().borders(Borders::ALL).title(format!(
+        "Debug Logs ({} entries{}{}) - ↑/↓: Scroll | E: Jump to End | T: Follow | F: Filter | S: Save",
+        entries.len(),
+        filter,
+        if app.debug_log_follow { ", following" } else { "" }
+    ));
+    let (rows, bottom) = wrapped_rows(&lines, wrap_area(app, block.inner(area)));
+    app.debug_log_rows.replace(rows);
+    app.debug_log_bottom.set(bottom);
+    mark_problem(&mut lines, app, Tab::DebugLogs);
+    let scroll = if app.debug_log_follow {
+        bottom
+    } else {
+        app.debug_log_scroll.min(bottom)
+    };
+    let paragraph = Paragraph::new(Text::from(lines))
         .wrap(Wrap { trim: false })
-        .scroll((app.debug_log_scroll as u16, 0));
-
-    f.render_widget(paragraph, area);
+        .scroll((scroll as u16, 0));
+    render_wrapped(f, app, block, paragraph, area);
 }
 
+const SPINNER_FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
 fn render_help(f: &mut Frame, app: &App, area: Rect) {
+    if let Some(task) = &app.pending_task {
+        let elapsed = task.started.elapsed();
+        let frame = SPINNER_FRAMES[(elapsed.as_millis() / 100) as usize % SPINNER_FRAMES.len()];
+        let status = Paragraph::new(format!(
+            "{} {}... ({}s) | ESC: Cancel",
+            frame,
+            task.description,
+            elapsed.as_secs()
+        ))
+        .block(Block::default().borders(Borders::ALL))
+        .style(Style::default().fg(Color::Yellow))
+        .alignment(Alignment::Center);
+        f.render_widget(status, area);
+        return;
+    }
+
+    if app.tool_confirmation.is_some() {
+        let help = Paragraph::new("Y: Run Tool | N/ESC: Back to Form")
+            .block(Block::default().borders(Borders::ALL))
+            .style(Style::default().fg(Color::Gray))
+            .alignment(Alignment::Center);
+        f.render_widget(help, area);
+        return;
+    }
+
+    if app.text_prompt.is_some() {
+        let help = Paragraph::new("Type: Enter Value | ENTER: Apply | ESC: Cancel")
+            .block(Block::default().borders(Borders::ALL))
+            .style(Style::default().fg(Color::Gray))
+            .alignment(Alignment::Center);
+        f.render_widget(help, area);
+        return;
+    }
+
     let help_text = match (app.tool_call_input_mode, app.prompt_input_mode, &app.detail_view, app.current_tab) {
+        (true, _, _, _) if app.raw_json_mode =>
+            "Type: Edit JSON | ALT+ENTER: New Line | ↑/↓: Scroll | CTRL+J: Form | ENTER: Execute | ESC: Cancel",
         (true, _, _, _) =>
-            "TAB/Shift+TAB: Navigate Fields | ↑/↓: Scroll | Type: Enter Value | ENTER: Execute | ESC: Cancel",
+            "TAB/Shift+TAB: Navigate Fields | ↑/↓: Scroll | Type: Enter Value | CTRL+J: Raw JSON | ENTER: Execute | ESC: Cancel",
         (_, true, _, _) =>
             "TAB/Shift+TAB: Navigate Fields | ↑/↓: Scroll | Type: Enter Value | ENTER: Get Prompt | ESC: Cancel",
+        (_, _, Some(_), _) if app.cached_request.is_some() =>
+            "↑/↓: Scroll | R: Refresh Cached Result | ESC: Close | ?: Help | Q: Quit",
+        (_, _, Some(detail), _) if detail.is_truncated() =>
+            "↑/↓: Scroll | F: View Full | W: Save to File | ESC: Close | ?: Help | Q: Quit",
+        (_, _, Some(detail), Tab::Tools) if detail.has_sections() =>
+            "↑/↓: Scroll | N/P: Next/Prev Item | Z: Collapse Item | V: Collapse All | C: Call Tool | ESC: Close | ?: Help | Q: Quit",
+        (_, _, Some(_), Tab::Tools) if app.item_detail_shown() =>
+            "↑/↓: Scroll | C: Call Tool | J: Raw JSON | ESC: Close | ?: Help | Q: Quit",
         (_, _, Some(_), Tab::Tools) =>
-            "↑/↓: Scroll | C: Call Tool | ESC: Close | Q: Quit",
+            "↑/↓: Scroll | C: Call Tool | ESC: Close | ?: Help | Q: Quit",
+        (_, _, Some(_), Tab::Prompts) if !app.embedded_resources.is_empty() =>
+            "↑/↓: Scroll | 1-9: Read Embedded Resource | C: Get Prompt | ESC: Close | ?: Help | Q: Quit",
+        (_, _, Some(_), Tab::Prompts) if app.item_detail_shown() =>
+            "↑/↓: Scroll | C: Get Prompt | J: Raw JSON | ESC: Close | ?: Help | Q: Quit",
+        (_, _, Some(_), Tab::Prompts) if app.prompt_json_arguments.is_some() =>
+            "↑/↓: Scroll | A: Use as Tool Arguments | C: Get Prompt | ESC: Close | ?: Help | Q: Quit",
         (_, _, Some(_), Tab::Prompts) =>
-            "↑/↓: Scroll | C: Get Prompt | ESC: Close | Q: Quit",
+            "↑/↓: Scroll | C: Get Prompt | ESC: Close | ?: Help | Q: Quit",
+        (_, _, Some(_), Tab::Resources) if app.item_detail_shown() =>
+            "↑/↓: Scroll | C: Read Resource | J: Raw JSON | ESC: Close | ?: Help | Q: Quit",
         (_, _, Some(_), Tab::Resources) =>
-            "↑/↓: Scroll | C: Read Resource | ESC: Close | Q: Quit",
+            "↑/↓: Scroll | C: Read Resource | X: Hex Dump | ESC: Close | ?: Help | Q: Quit",
+        (_, _, Some(_), _) if app.item_detail_shown() =>
+            "↑/↓: Scroll | J: Raw JSON | ESC: Close | ?: Help | Q: Quit",
         (_, _, Some(_), _) =>
-            "↑/↓: Scroll | ESC: Close | Q: Quit",
+            "↑/↓: Scroll | ESC: Close | ?: Help | Q: Quit",
         (_, _, None, Tab::ServerLogs) =>
-            "TAB: Next Tab | ←/→: Switch Tabs | ↑/↓: Scroll | E: Jump to End | S: Save Logs | R: Refresh | Q: Quit",
+            "TAB: Next Tab | ←/→: Switch Tabs | ↑/↓: Scroll | N/P: Problems | E: Jump to End | T: Follow | S: Save Logs | R: Refresh | ?: Help | Q: Quit",
         (_, _, None, Tab::DebugLogs) =>
-            "TAB: Next Tab | ←/→: Switch Tabs | ↑/↓: Scroll | E: Jump to End | S: Save Logs | R: Refresh | Q: Quit",
+            "TAB: Next Tab | ←/→: Switch Tabs | ↑/↓: Scroll | N/P: Problems | E: Jump to End | T: Follow | F: Filter by ID | S: Save Logs | R: Refresh | ?: Help | Q: Quit",
         (_, _, None, Tab::ServerInfo) =>
-            "TAB: Next Tab | ←/→: Switch Tabs | ↑/↓: Scroll | ENTER: Details | R: Refresh | Q: Quit",
+            "TAB: Next Tab | ←/→: Switch Tabs | ↑/↓: Scroll | ENTER: Details | I: Re-initialize | S: Save JSON | R: Refresh | ?: Help | Q: Quit",
+        (_, _, None, Tab::Tools) if app.settings.tool_table =>
+            "TAB: Next Tab | ←/→: Switch Tabs | ↑/↓: Navigate | ENTER: Details | C: Call Tool | O: Sort | M: Recent | T: List | R: Refresh | ?: Help | Q: Quit",
         (_, _, None, Tab::Tools) =>
-            "TAB: Next Tab | ←/→: Switch Tabs | ↑/↓: Navigate | ENTER: Details | C: Call Tool | R: Refresh | Q: Quit",
+            "TAB: Next Tab | ←/→: Switch Tabs | ↑/↓: Navigate | ENTER: Details | C: Call Tool | D: Dense | M: Recent | T: Table | R: Refresh | ?: Help | Q: Quit",
         (_, _, None, Tab::Prompts) =>
-            "TAB: Next Tab | ←/→: Switch Tabs | ↑/↓: Navigate | ENTER: Details | C: Get Prompt | R: Refresh | Q: Quit",
+            "TAB: Next Tab | ←/→: Switch Tabs | ↑/↓: Navigate | ENTER: Details | C: Get Prompt | D: Dense | R: Refresh | ?: Help | Q: Quit",
         (_, _, None, Tab::Resources) =>
-            "TAB: Next Tab | ←/→: Switch Tabs | ↑/↓: Navigate | ENTER: Details | C: Read Resource | R: Refresh | Q: Quit",
+            "TAB: Next Tab | ←/→: Switch Tabs | ↑/↓: Navigate | ENTER: Details | C: Read Resource | U: Read URI | D: Dense | R: Refresh | ?: Help | Q: Quit",
     };
 
     let help = Paragraph::new(help_text)
@@ -471,8 +1086,11 @@ fn render_help(f: &mut Frame, app: &App, area: Rect) {
 fn render_tool_input_form(f: &mut Frame, app: &App) {
     // Calculate centered popup area
     let area = f.area();
-    let popup_width = area.width.saturating_sub(10).min(80);
-    let popup_height = (app.input_fields.len() as u16 * 3 + 8).min(area.height.saturating_sub(4));
+    let InputPopup {
+        width: popup_width,
+        height: popup_height,
+        error_rows: error_height,
+    } = app.input_popup(area.width, area.height);
 
     let popup_area = Rect {
         x: (area.width.saturating_sub(popup_width)) / 2,
@@ -489,24 +1107,43 @@ fn render_tool_input_form(f: &mut Frame, app: &App) {
         .borders(Borders::ALL)
         .border_style(Style::default().fg(Color::Yellow))
         .title(format!(
-            "Call Tool: {}",
+            "Call Tool: {}{}",
             app.tools
                 .get(app.selected_tool)
                 .map(|t| t.name.as_str())
-                .unwrap_or("")
+                .unwrap_or(""),
+            if app.raw_json_mode { " (raw JSON)" } else { "" }
         ))
         .style(Style::default().bg(Color::Black));
     f.render_widget(block, popup_area);
 
     // Inner area for content
-    let inner = Rect {
+    let mut inner = Rect {
         x: popup_area.x + 2,
         y: popup_area.y + 2,
         width: popup_area.width.saturating_sub(4),
         height: popup_area.height.saturating_sub(4),
     };
 
-    if app.input_fields.is_empty() {
+    if let Some(error) = &app.tool_call_error {
+        let error_area = Rect {
+            height: error_height.saturating_sub(1).min(inner.height),
+            ..inner
+        };
+        let paragraph = Paragraph::new(Span::styled(
+            error.as_str(),
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        ))
+        .wrap(Wrap { trim: true });
+        f.render_widget(paragraph, error_area);
+
+        inner.y += error_height.min(inner.height);
+        inner.height = inner.height.saturating_sub(error_height);
+    }
+
+    if app.raw_json_mode {
+        render_raw_json_editor(f, app, inner);
+    } else if app.input_fields.is_empty() {
         // No parameters needed
         let text = vec![
             Line::from("This tool has no parameters."),
@@ -529,14 +1166,22 @@ fn render_tool_input_form(f: &mut Frame, app: &App) {
                 .map(|s| s.as_str())
                 .unwrap_or("");
 
+            let has_error = app.tool_call_error_field.as_deref() == Some(field.name.as_str());
+            let field_type = match &field.item_type {
+                Some(item_type) => format!("{} list, comma-separated", item_type),
+                None => field.field_type.clone(),
+            };
             let field_label = format!(
-                "{} ({}{})",
+                "{}{} ({}{})",
+                if has_error { "⚠ " } else { "" },
                 field.name,
-                field.field_type,
+                field_type,
                 if field.required { ", required" } else { "" }
             );
 
-            let label_style = if is_current {
+            let label_style = if has_error {
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+            } else if is_current {
                 Style::default()
                     .fg(Color::Yellow)
                     .add_modifier(Modifier::BOLD)
@@ -561,23 +1206,14 @@ fn render_tool_input_form(f: &mut Frame, app: &App) {
                 Style::default().fg(Color::Cyan)
             };
 
-            let display_value = if is_current && value.is_empty() {
-                "_"
-            } else if value.is_empty() {
+            let display_value = if value.is_empty() && !is_current {
                 "(empty)"
             } else {
                 value
             };
+            let cursor = is_current.then(|| app.field_cursor(&field.name, value));
 
-            lines.push(Line::from(vec![
-                Span::raw("  "),
-                Span::styled(display_value, value_style),
-                if is_current {
-                    Span::styled("█", Style::default().fg(Color::Green))
-                } else {
-                    Span::raw("")
-                },
-            ]));
+            push_value_lines(&mut lines, "  ", display_value, value_style, cursor);
 
             if i < app.input_fields.len() - 1 {
                 lines.push(Line::from(""));
@@ -592,11 +1228,92 @@ fn render_tool_input_form(f: &mut Frame, app: &App) {
     }
 }
 
+/// Render a (possibly multi-line) value with each line indented, drawing the
+/// caret at `cursor` (a character index) when the input is focused.
+fn push_value_lines<'a>(
+    lines: &mut Vec<Line<'a>>,
+    indent: &'a str,
+    value: &'a str,
+    style: Style,
+    cursor: Option<usize>,
+) {
+    let mut offset = 0;
+    for value_line in value.split('\n') {
+        let len = value_line.chars().count();
+        let line_cursor = cursor
+            .filter(|c| (offset..=offset + len).contains(c))
+            .map(|c| c - offset);
+
+        let mut spans = vec![Span::raw(indent)];
+        spans.extend(cursor_spans(value_line, style, line_cursor));
+        lines.push(Line::from(spans));
+
+        offset += len + 1;
+    }
+}
+
+/// Split a line of text around the caret. At the end of the text the caret
+/// is a block; inside the text the character under it is shown reversed.
+fn cursor_spans(text: &str, style: Style, cursor: Option<usize>) -> Vec<Span<'_>> {
+    let Some(cursor) = cursor else {
+        return vec![Span::styled(text, style)];
+    };
+
+    let mut chars = text.char_indices().skip(cursor);
+    match chars.next() {
+        Some((start, c)) => {
+            let end = start + c.len_utf8();
+            vec![
+                Span::styled(&text[..start], style),
+                Span::styled(&text[start..end], style.add_modifier(Modifier::REVERSED)),
+                Span::styled(&text[end..], style),
+            ]
+        }
+        None => vec![
+            Span::styled(text, style),
+            Span::styled("█", Style::default().fg(Color::Green)),
+        ],
+    }
+}
+
+fn render_raw_json_editor(f: &mut Frame, app: &App, area: Rect) {
+    let mut lines = vec![
+        Line::from(Span::styled(
+            "Arguments (JSON object)",
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )),
+        Line::from(""),
+    ];
+
+    let value_style = Style::default()
+        .fg(Color::Green)
+        .add_modifier(Modifier::BOLD);
+    let cursor = app.raw_json_cursor.min(app.raw_json_input.chars().count());
+    push_value_lines(
+        &mut lines,
+        "",
+        &app.raw_json_input,
+        value_style,
+        Some(cursor),
+    );
+
+    let paragraph = Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .scroll((app.tool_input_scroll as u16, 0));
+
+    f.render_widget(paragraph, area);
+}
+
 fn render_prompt_input_form(f: &mut Frame, app: &App) {
     // Calculate centered popup area
     let area = f.area();
-    let popup_width = area.width.saturating_sub(10).min(80);
-    let popup_height = (app.input_fields.len() as u16 * 3 + 8).min(area.height.saturating_sub(4));
+    let InputPopup {
+        width: popup_width,
+        height: popup_height,
+        ..
+    } = app.input_popup(area.width, area.height);
 
     let popup_area = Rect {
         x: (area.width.saturating_sub(popup_width)) / 2,
@@ -685,23 +1402,14 @@ fn render_prompt_input_form(f: &mut Frame, app: &App) {
                 Style::default().fg(Color::Cyan)
             };
 
-            let display_value = if is_current && value.is_empty() {
-                "_"
-            } else if value.is_empty() {
+            let display_value = if value.is_empty() && !is_current {
                 "(empty)"
             } else {
                 value
             };
+            let cursor = is_current.then(|| app.field_cursor(&field.name, value));
 
-            lines.push(Line::from(vec![
-                Span::raw("  "),
-                Span::styled(display_value, value_style),
-                if is_current {
-                    Span::styled("█", Style::default().fg(Color::Green))
-                } else {
-                    Span::raw("")
-                },
-            ]));
+            push_value_lines(&mut lines, "  ", display_value, value_style, cursor);
 
             if i < app.input_fields.len() - 1 {
                 lines.push(Line::from(""));
@@ -715,3 +1423,85 @@ fn render_prompt_input_form(f: &mut Frame, app: &App) {
         f.render_widget(paragraph, inner);
     }
 }
+
+fn render_text_prompt(f: &mut Frame, prompt: &TextPrompt) {
+    // Calculate centered popup area
+    let area = f.area();
+    let popup_width = area.width.saturating_sub(10).min(80);
+    let popup_height = 5.min(area.height);
+
+    let popup_area = Rect {
+        x: (area.width.saturating_sub(popup_width)) / 2,
+        y: (area.height.saturating_sub(popup_height)) / 2,
+        width: popup_width,
+        height: popup_height,
+    };
+
+    // Clear the background to create a solid opaque popup
+    f.render_widget(Clear, popup_area);
+
+    let cursor = prompt.cursor.min(prompt.value.chars().count());
+    let line = Line::from(cursor_spans(
+        &prompt.value,
+        Style::default()
+            .fg(Color::Green)
+            .add_modifier(Modifier::BOLD),
+        Some(cursor),
+    ));
+
+    let paragraph = Paragraph::new(line).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Yellow))
+            .title(prompt.title.as_str())
+            .padding(Padding::horizontal(1))
+            .style(Style::default().bg(Color::Black)),
+    );
+
+    f.render_widget(paragraph, popup_area);
+}
+
+fn render_tool_confirmation(f: &mut Frame, confirmation: &ToolConfirmation) {
+    // Calculate centered popup area
+    let area = f.area();
+    let popup_width = area.width.saturating_sub(10).min(60);
+    let popup_height = 7.min(area.height);
+
+    let popup_area = Rect {
+        x: (area.width.saturating_sub(popup_width)) / 2,
+        y: (area.height.saturating_sub(popup_height)) / 2,
+        width: popup_width,
+        height: popup_height,
+    };
+
+    // Clear the background to create a solid opaque popup
+    f.render_widget(Clear, popup_area);
+
+    let text = vec![
+        Line::from(format!(
+            "Tool '{}' {}.",
+            confirmation.tool_name, confirmation.reason
+        )),
+        Line::from(""),
+        Line::from(Span::styled(
+            "Run it? (y/n)",
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )),
+    ];
+
+    let paragraph = Paragraph::new(text)
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true })
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Red))
+                .title("Confirm Tool Call")
+                .padding(Padding::horizontal(1))
+                .style(Style::default().bg(Color::Black)),
+        );
+
+    f.render_widget(paragraph, popup_area);
+}
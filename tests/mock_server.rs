@@ -0,0 +1,406 @@
+//! End-to-end tests of `McpClient` against a mock MCP server over stdio.
+//!
+//! The test binary is its own server: started with `--serve` it speaks MCP on
+//! stdin/stdout instead of running the tests. It uses a small runner of its
+//! own (`harness = false`) so nothing else is written to stdout in that mode.
+
+use anyhow::{bail, ensure, Context, Result};
+use mcpeek::mcp::protocol::{ResourceContents, ToolContent};
+use mcpeek::mcp::{IdScheme, McpClient};
+use mcpeek::tui::app::InitialToolCall;
+use mcpeek::tui::{App, Tab};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::future::Future;
+use std::io::{BufRead, Write};
+use std::pin::Pin;
+use std::process::ExitCode;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How long the `slow` tool takes to answer.
+const SLOW_TOOL_DELAY: Duration = Duration::from_millis(300);
+
+/// Longest a single test may run before it counts as hung.
+const TEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+type TestFuture = Pin<Box<dyn Future<Output = Result<()>>>>;
+
+macro_rules! tests {
+    ($($name:ident),* $(,)?) => {
+        [$((stringify!($name), (|| Box::pin($name()) as TestFuture) as fn() -> TestFuture)),*]
+    };
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("--serve") {
+        serve();
+        return ExitCode::SUCCESS;
+    }
+
+    let tests = tests![
+        initialize_reports_server_info,
+        lists_tools_prompts_and_resources,
+        lists_all_in_one_batch,
+        call_tool_returns_content,
+        concurrent_calls_get_their_own_responses,
+        string_ids_are_matched,
+        get_prompt_and_read_resource,
+        server_errors_are_reported,
+        server_exit_fails_pending_request,
+        oversized_response_fails_request,
+        tab_switches_keep_startup_lists,
+    ];
+
+    // Like libtest, run only the tests whose name contains a filter argument
+    let filters: Vec<&String> = args.iter().filter(|arg| !arg.starts_with('-')).collect();
+    let selected: Vec<_> = tests
+        .iter()
+        .filter(|(name, _)| filters.is_empty() || filters.iter().any(|f| name.contains(f.as_str())))
+        .collect();
+
+    let runtime = tokio::runtime::Runtime::new().expect("Failed to start the tokio runtime");
+    println!("\nrunning {} tests", selected.len());
+    let mut failures = Vec::new();
+    for (name, test) in &selected {
+        let result = runtime.block_on(async {
+            tokio::time::timeout(TEST_TIMEOUT, test())
+                .await
+                .unwrap_or_else(|_| bail!("timed out after {:?}", TEST_TIMEOUT))
+        });
+        match result {
+            Ok(()) => println!("test {} ... ok", name),
+            Err(e) => {
+                println!("test {} ... FAILED", name);
+                failures.push((name, e));
+            }
+        }
+    }
+
+    for (name, error) in &failures {
+        println!("\n---- {} ----\n{:?}", name, error);
+    }
+    println!(
+        "\ntest result: {}. {} passed; {} failed\n",
+        if failures.is_empty() { "ok" } else { "FAILED" },
+        selected.len() - failures.len(),
+        failures.len()
+    );
+    if failures.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+/// Start this binary as the mock server.
+async fn spawn_mock() -> Result<McpClient> {
+    let exe = std::env::current_exe().context("Failed to find the test binary")?;
+    let exe = exe.to_str().context("Test binary path is not UTF-8")?;
+    McpClient::new(exe, &["--serve".to_string()]).await
+}
+
+async fn initialize_reports_server_info() -> Result<()> {
+    let client = spawn_mock().await?;
+    let info = client.initialize().await?;
+    ensure!(info.server_info.name == "mock", "{:?}", info.server_info);
+    ensure!(info.capabilities.tools.is_some());
+    ensure!(client.get_server_info().await.is_some());
+    client.shutdown().await
+}
+
+async fn lists_tools_prompts_and_resources() -> Result<()> {
+    let client = spawn_mock().await?;
+    client.initialize().await?;
+
+    let tools: Vec<String> = client
+        .list_tools()
+        .await?
+        .into_iter()
+        .map(|t| t.name)
+        .collect();
+    ensure!(
+        tools == ["echo", "slow", "fail", "exit", "big"],
+        "{:?}",
+        tools
+    );
+    let prompts = client.list_prompts().await?;
+    ensure!(
+        prompts.len() == 1 && prompts[0].name == "greet",
+        "{:?}",
+        prompts
+    );
+    let resources = client.list_resources().await?;
+    ensure!(
+        resources.len() == 1 && resources[0].uri == "mock://readme",
+        "{:?}",
+        resources
+    );
+    client.shutdown().await
+}
+
+async fn lists_all_in_one_batch() -> Result<()> {
+    let client = spawn_mock().await?;
+    client.initialize().await?;
+
+    let lists = client.list_all(true).await;
+    ensure!(lists.tools.context("tools missing")??.len() == 5);
+    ensure!(lists.prompts.context("prompts missing")??.len() == 1);
+    ensure!(lists.resources.context("resources missing")??.len() == 1);
+    client.shutdown().await
+}
+
+async fn call_tool_returns_content() -> Result<()> {
+    let client = spawn_mock().await?;
+    client.initialize().await?;
+
+    let result = client
+        .call_tool("echo", Some(text_argument("hello")))
+        .await?;
+    ensure!(tool_text(&result.content) == "hello");
+    ensure!(result.is_error != Some(true));
+
+    let result = client.call_tool("fail", None).await?;
+    ensure!(result.is_error == Some(true));
+    client.shutdown().await
+}
+
+async fn concurrent_calls_get_their_own_responses() -> Result<()> {
+    let client = spawn_mock().await?;
+    client.initialize().await?;
+
+    // The slow call is answered after the echo that was sent after it
+    let (slow, echo) = tokio::join!(
+        client.call_tool("slow", None),
+        client.call_tool("echo", Some(text_argument("fast"))),
+    );
+    ensure!(tool_text(&slow?.content) == "slow");
+    ensure!(tool_text(&echo?.content) == "fast");
+    client.shutdown().await
+}
+
+async fn string_ids_are_matched() -> Result<()> {
+    let client = spawn_mock().await?.with_id_scheme(IdScheme::String);
+    client.initialize().await?;
+
+    let result = client.call_tool("echo", Some(text_argument("id"))).await?;
+    ensure!(tool_text(&result.content) == "id");
+    client.shutdown().await
+}
+
+async fn get_prompt_and_read_resource() -> Result<()> {
+    let client = spawn_mock().await?;
+    client.initialize().await?;
+
+    let arguments = HashMap::from([("name".to_string(), "Ada".to_string())]);
+    let prompt = client.get_prompt("greet", Some(arguments)).await?;
+    let message = serde_json::to_value(&prompt.messages[0].content)?;
+    ensure!(message["text"] == "Hello, Ada!", "{}", message);
+
+    let contents = client.read_resource("mock://readme").await?;
+    match contents.as_slice() {
+        [ResourceContents::Text { uri, text, .. }] => {
+            ensure!(uri == "mock://readme" && text == "Read me");
+        }
+        other => bail!("unexpected contents: {:?}", other),
+    }
+    client.shutdown().await
+}
+
+async fn server_errors_are_reported() -> Result<()> {
+    let client = spawn_mock().await?;
+    client.initialize().await?;
+
+    let error = client.read_resource("mock://missing").await.unwrap_err();
+    ensure!(
+        format!("{:#}", error).contains("Resource not found"),
+        "{:#}",
+        error
+    );
+
+    // The connection is still usable after an error
+    client.list_tools().await?;
+    client.shutdown().await
+}
+
+async fn server_exit_fails_pending_request() -> Result<()> {
+    let client = spawn_mock().await?;
+    client.initialize().await?;
+
+    let started = Instant::now();
+    ensure!(client.call_tool("exit", None).await.is_err());
+    ensure!(client.list_tools().await.is_err());
+    // Well before the 30s request timeout
+    ensure!(started.elapsed() < Duration::from_secs(5));
+    client.shutdown().await
+}
+
+async fn oversized_response_fails_request() -> Result<()> {
+    let client = spawn_mock().await?.with_max_response_bytes(4096);
+    client.initialize().await?;
+
+    let error = client.call_tool("big", None).await.unwrap_err();
+    ensure!(
+        format!("{:#}", error).contains("--max-response-bytes"),
+        "{:#}",
+        error
+    );
+
+    // The rest of the stream is still read
+    let result = client
+        .call_tool("echo", Some(text_argument("after")))
+        .await?;
+    ensure!(tool_text(&result.content) == "after");
+    client.shutdown().await
+}
+
+async fn tab_switches_keep_startup_lists() -> Result<()> {
+    let client = Arc::new(spawn_mock().await?);
+    client.initialize().await?;
+
+    let mut app = App::new(false);
+    app.ready_delay = Duration::from_millis(200);
+    app.initial_tool_call = Some(InitialToolCall {
+        tool_name: "echo".to_string(),
+        arguments: vec![("text".to_string(), "hi".to_string())],
+        execute: false,
+    });
+    app.preload_lists(&client);
+    // Loads only clear their own tab's failures, not this
+    app.update_connection(true);
+    // Switch through the list tabs to the logs before the lists arrive
+    for _ in 0..4 {
+        app.next_tab();
+        app.load_data(&client);
+    }
+    ensure!(app.current_tab == Tab::ServerLogs);
+
+    while app.loading {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        app.poll_tasks();
+    }
+    ensure!(app.tools.len() == 5 && app.prompts.len() == 1 && app.resources.len() == 1);
+    ensure!(app.current_tab == Tab::Tools && app.tool_call_input_mode);
+    ensure!(app.tool_call_inputs.get("text").map(String::as_str) == Some("hi"));
+    ensure!(
+        app.error_message.as_deref()
+            == Some("Lost the connection to the server; check Server Logs for why"),
+        "{:?}",
+        app.error_message
+    );
+    client.shutdown().await
+}
+
+fn text_argument(text: &str) -> HashMap<String, Value> {
+    HashMap::from([("text".to_string(), json!(text))])
+}
+
+fn tool_text(content: &[ToolContent]) -> String {
+    content
+        .iter()
+        .filter_map(|item| match item {
+            ToolContent::Text { text } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Serve MCP on stdin/stdout until stdin closes. Each request is handled on
+/// its own thread, so a slow one doesn't hold up the rest.
+fn serve() {
+    let stdout = Arc::new(Mutex::new(std::io::stdout()));
+    for line in std::io::stdin().lock().lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        let Ok(message) = serde_json::from_str::<Value>(&line) else {
+            continue;
+        };
+        let stdout = stdout.clone();
+        std::thread::spawn(move || {
+            let reply = match message {
+                Value::Array(batch) => {
+                    let replies: Vec<Value> = batch.iter().filter_map(handle).collect();
+                    (!replies.is_empty()).then_some(Value::Array(replies))
+                }
+                message => handle(&message),
+            };
+            if let Some(reply) = reply {
+                let mut stdout = stdout.lock().unwrap();
+                let _ = writeln!(stdout, "{}", reply);
+                let _ = stdout.flush();
+            }
+        });
+    }
+}
+
+/// Answer one JSON-RPC message, or `None` for notifications.
+fn handle(message: &Value) -> Option<Value> {
+    let id = message.get("id")?.clone();
+    let params = &message["params"];
+    let result = match message["method"].as_str().unwrap_or_default() {
+        "initialize" => Ok(json!({
+            "protocolVersion": params["protocolVersion"],
+            "capabilities": {"tools": {}, "prompts": {}, "resources": {}},
+            "serverInfo": {"name": "mock", "version": "1.0.0"}
+        })),
+        "tools/list" => Ok(json!({"tools": [
+            {
+                "name": "echo",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": {"text": {"type": "string"}},
+                    "required": ["text"]
+                }
+            },
+            {"name": "slow", "inputSchema": {"type": "object"}},
+            {"name": "fail", "inputSchema": {"type": "object"}},
+            {"name": "exit", "inputSchema": {"type": "object"}},
+            {"name": "big", "inputSchema": {"type": "object"}}
+        ]})),
+        "tools/call" => match params["name"].as_str().unwrap_or_default() {
+            "echo" => Ok(text_result(
+                params["arguments"]["text"].as_str().unwrap_or_default(),
+            )),
+            "slow" => {
+                std::thread::sleep(SLOW_TOOL_DELAY);
+                Ok(text_result("slow"))
+            }
+            "fail" => Ok(json!({"content": [{"type": "text", "text": "failed"}], "isError": true})),
+            "exit" => std::process::exit(0),
+            "big" => Ok(text_result(&"x".repeat(100_000))),
+            name => Err((-32602, format!("Unknown tool: {}", name))),
+        },
+        "prompts/list" => Ok(json!({"prompts": [
+            {"name": "greet", "arguments": [{"name": "name", "required": true}]}
+        ]})),
+        "prompts/get" => Ok(json!({"messages": [{
+            "role": "user",
+            "content": {
+                "type": "text",
+                "text": format!("Hello, {}!", params["arguments"]["name"].as_str().unwrap_or("you"))
+            }
+        }]})),
+        "resources/list" => Ok(json!({"resources": [
+            {"uri": "mock://readme", "name": "readme", "mimeType": "text/plain"}
+        ]})),
+        "resources/read" => match params["uri"].as_str() {
+            Some("mock://readme") => Ok(json!({"contents": [
+                {"uri": "mock://readme", "mimeType": "text/plain", "text": "Read me"}
+            ]})),
+            _ => Err((-32002, "Resource not found".to_string())),
+        },
+        method => Err((-32601, format!("Method not found: {}", method))),
+    };
+    Some(match result {
+        Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+        Err((code, message)) => {
+            json!({"jsonrpc": "2.0", "id": id, "error": {"code": code, "message": message}})
+        }
+    })
+}
+
+fn text_result(text: &str) -> Value {
+    json!({"content": [{"type": "text", "text": text}]})
+}